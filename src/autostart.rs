@@ -0,0 +1,310 @@
+use std::path::PathBuf;
+#[cfg(not(windows))]
+use std::{fs, io::ErrorKind};
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use tauri::{AppHandle, Manager, Webview};
+use tracing::info;
+
+use crate::{cli::AutostartCommand, launcher::Launcher};
+
+/// Whether the launcher starts with the user session.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct AutostartStatus {
+    pub enabled: bool,
+    /// Started with `--minimized`
+    pub minimized: bool,
+}
+
+/// Arguments the launcher is started with at login.
+fn launch_args(profile: Option<&str>, minimized: bool) -> Vec<String> {
+    let mut args = Vec::new();
+    if let Some(profile) = profile {
+        args.extend(["--profile".to_owned(), profile.to_owned()]);
+    }
+    if minimized {
+        args.push("--minimized".to_owned());
+    }
+    args
+}
+
+fn current_exe() -> Result<PathBuf> {
+    Ok(std::env::current_exe()?)
+}
+
+/// Runs `alas-launcher autostart ...` and exits.
+pub fn run_cli(profile: Option<&str>, command: AutostartCommand) -> ! {
+    let result = match command {
+        AutostartCommand::Enable { minimized } => {
+            enable(profile, minimized).map(|()| "Enabled start at login".to_owned())
+        }
+        AutostartCommand::Disable => {
+            disable(profile).map(|()| "Disabled start at login".to_owned())
+        }
+        AutostartCommand::Status => status(profile).map(|status| match status {
+            AutostartStatus { enabled: false, .. } => "Start at login is disabled".to_owned(),
+            AutostartStatus {
+                minimized: true, ..
+            } => "Start at login is enabled, minimized".to_owned(),
+            AutostartStatus { .. } => "Start at login is enabled".to_owned(),
+        }),
+    };
+    match result {
+        Ok(message) => {
+            println!("{message}");
+            std::process::exit(0);
+        }
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    }
+}
+
+#[tauri::command]
+pub fn autostart_status(app_handle: AppHandle) -> Result<AutostartStatus, String> {
+    status(app_handle.state::<Launcher>().profile()).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn set_autostart(
+    app_handle: AppHandle,
+    webview: Webview,
+    enabled: bool,
+    minimized: bool,
+) -> Result<(), String> {
+    crate::settings_window::check_caller(&webview)?;
+    let launcher = app_handle.state::<Launcher>();
+    let profile = launcher.profile();
+    let result = if enabled {
+        enable(profile, minimized)
+    } else {
+        disable(profile)
+    };
+    result.map_err(|e| e.to_string())
+}
+
+// XDG autostart entry
+#[cfg(all(unix, not(target_os = "macos")))]
+fn entry_path(profile: Option<&str>) -> Result<PathBuf> {
+    let name = match profile {
+        Some(profile) => format!("alas-launcher-{profile}.desktop"),
+        None => "alas-launcher.desktop".to_owned(),
+    };
+    let dir = dirs::config_dir().ok_or_else(|| anyhow!("No config folder on this system"))?;
+    Ok(dir.join("autostart").join(name))
+}
+
+/// Quotes arguments for the `Exec` key of a desktop entry.
+///
+/// The key is unescaped as a string before it's split into quoted arguments, so the backslash
+/// escaping a character in quotes has to be escaped itself.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn desktop_exec(args: &[String]) -> String {
+    args.iter()
+        .map(|arg| {
+            let mut quoted = String::from("\"");
+            for ch in arg.chars() {
+                match ch {
+                    '"' | '`' | '$' => quoted.extend(['\\', '\\', ch]),
+                    '\\' => quoted.push_str(r"\\\\"),
+                    '%' => quoted.push_str("%%"),
+                    _ => quoted.push(ch),
+                }
+            }
+            quoted.push('"');
+            quoted
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn entry_content(profile: Option<&str>, minimized: bool) -> Result<String> {
+    let mut args = vec![current_exe()?.to_string_lossy().into_owned()];
+    args.extend(launch_args(profile, minimized));
+    let name = match profile {
+        Some(profile) => format!("Alas Launcher ({profile})"),
+        None => "Alas Launcher".to_owned(),
+    };
+    Ok(format!(
+        "[Desktop Entry]\n\
+         Type=Application\n\
+         Name={name}\n\
+         Exec={}\n\
+         Terminal=false\n\
+         X-GNOME-Autostart-enabled=true\n",
+        desktop_exec(&args)
+    ))
+}
+
+// LaunchAgent
+#[cfg(target_os = "macos")]
+fn entry_path(profile: Option<&str>) -> Result<PathBuf> {
+    let label = match profile {
+        Some(profile) => format!("moe.taiho.alas-launcher.{profile}"),
+        None => "moe.taiho.alas-launcher".to_owned(),
+    };
+    let home = dirs::home_dir().ok_or_else(|| anyhow!("No home folder on this system"))?;
+    Ok(home
+        .join("Library/LaunchAgents")
+        .join(format!("{label}.plist")))
+}
+
+#[cfg(target_os = "macos")]
+fn entry_content(profile: Option<&str>, minimized: bool) -> Result<String> {
+    let path = entry_path(profile)?;
+    let label = path.file_stem().unwrap_or_default().to_string_lossy();
+    let mut args = vec![current_exe()?.to_string_lossy().into_owned()];
+    args.extend(launch_args(profile, minimized));
+    let args = args
+        .iter()
+        .map(|arg| format!("    <string>{}</string>\n", crate::escape_html(arg)))
+        .collect::<String>();
+    Ok(format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+  <key>Label</key>
+  <string>{label}</string>
+  <key>ProgramArguments</key>
+  <array>
+{args}  </array>
+  <key>RunAtLoad</key>
+  <true/>
+</dict>
+</plist>
+"#
+    ))
+}
+
+#[cfg(not(windows))]
+fn enable(profile: Option<&str>, minimized: bool) -> Result<()> {
+    let path = entry_path(profile)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&path, entry_content(profile, minimized)?)?;
+    info!("Wrote {}", path.display());
+    Ok(())
+}
+
+#[cfg(not(windows))]
+fn disable(profile: Option<&str>) -> Result<()> {
+    let path = entry_path(profile)?;
+    match fs::remove_file(&path) {
+        Ok(()) => {
+            info!("Removed {}", path.display());
+            Ok(())
+        }
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(anyhow!("Failed to remove {}: {e}", path.display())),
+    }
+}
+
+#[cfg(not(windows))]
+fn status(profile: Option<&str>) -> Result<AutostartStatus> {
+    match fs::read_to_string(entry_path(profile)?) {
+        Ok(content) => Ok(AutostartStatus {
+            enabled: true,
+            minimized: content.contains("--minimized"),
+        }),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(AutostartStatus {
+            enabled: false,
+            minimized: false,
+        }),
+        Err(e) => Err(e.into()),
+    }
+}
+
+// Run key in the registry
+#[cfg(windows)]
+const RUN_KEY: &str = r"HKCU\Software\Microsoft\Windows\CurrentVersion\Run";
+
+#[cfg(windows)]
+fn value_name(profile: Option<&str>) -> String {
+    match profile {
+        Some(profile) => format!("Alas Launcher ({profile})"),
+        None => "Alas Launcher".to_owned(),
+    }
+}
+
+#[cfg(windows)]
+fn reg(args: &[&str]) -> Result<std::process::Output> {
+    use crate::window_util::CreateNoWindow;
+    Ok(std::process::Command::new("reg")
+        .args(args)
+        .create_no_window()
+        .output()?)
+}
+
+#[cfg(windows)]
+fn enable(profile: Option<&str>, minimized: bool) -> Result<()> {
+    let mut command = format!("\"{}\"", current_exe()?.display());
+    for arg in launch_args(profile, minimized) {
+        command.push_str(&format!(" \"{arg}\""));
+    }
+    let name = value_name(profile);
+    let output = reg(&[
+        "add", RUN_KEY, "/v", &name, "/t", "REG_SZ", "/d", &command, "/f",
+    ])?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to add {name} to {RUN_KEY}: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    info!("Added {name} to {RUN_KEY}");
+    Ok(())
+}
+
+#[cfg(windows)]
+fn disable(profile: Option<&str>) -> Result<()> {
+    if !status(profile)?.enabled {
+        return Ok(());
+    }
+    let name = value_name(profile);
+    let output = reg(&["delete", RUN_KEY, "/v", &name, "/f"])?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to remove {name} from {RUN_KEY}: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    info!("Removed {name} from {RUN_KEY}");
+    Ok(())
+}
+
+#[cfg(windows)]
+fn status(profile: Option<&str>) -> Result<AutostartStatus> {
+    let output = reg(&["query", RUN_KEY, "/v", &value_name(profile)])?;
+    Ok(AutostartStatus {
+        enabled: output.status.success(),
+        minimized: String::from_utf8_lossy(&output.stdout).contains("--minimized"),
+    })
+}
+
+#[cfg(all(test, unix, not(target_os = "macos")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_desktop_exec() {
+        let args = |a: &[&str]| a.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            r#""/opt/alas launcher/alas-launcher" "--profile" "main" "--minimized""#,
+            desktop_exec(&args(&[
+                "/opt/alas launcher/alas-launcher",
+                "--profile",
+                "main",
+                "--minimized"
+            ]))
+        );
+        assert_eq!(
+            r#""/home/me/\\$HOME/50%%/a\\"b/\\`c\\\\d""#,
+            desktop_exec(&args(&[r#"/home/me/$HOME/50%/a"b/`c\d"#]))
+        );
+    }
+}
@@ -1,64 +1,434 @@
 use std::{
-    net::TcpStream,
-    process::{Command, ExitStatus},
-    thread::sleep,
-    time::Duration,
+    collections::VecDeque,
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    process::{Command, ExitStatus, Stdio},
+    sync::{
+        mpsc::{self, RecvTimeoutError, Sender},
+        Arc, Mutex,
+    },
+    thread::{self, sleep},
+    time::{Duration, Instant},
 };
 
 use anyhow::{anyhow, Result};
 use command_group::{CommandGroup, GroupChild};
-use tracing::warn;
+use tracing::{info, warn};
 
-use crate::window_util::CreateNoWindow as _;
+use crate::{
+    process_tree::{Leftover, ProcessTree},
+    settings::{Settings, MAX_GRACE_SECS},
+    webui::WebuiOptions,
+    window_util::CreateNoWindow as _,
+};
+
+/// How long each step of the shutdown ladder waits for gui.py to exit before escalating.
+///
+/// gui.py is first interrupted (SIGINT, like Ctrl-C in a terminal, or Ctrl-Break on Windows)
+/// so running ALAS instances get a chance to finish writing their configs, then terminated,
+/// then killed.
+pub struct ShutdownPolicy {
+    pub grace: Duration,
+    pub term_grace: Duration,
+}
+
+impl ShutdownPolicy {
+    /// Policy from the launcher settings, with the grace period overridable by
+    /// `ALAS_LAUNCHER_SHUTDOWN_GRACE` (seconds, at most `MAX_GRACE_SECS`).
+    pub fn new(settings: &Settings) -> Self {
+        let mut policy = Self {
+            grace: Duration::from_secs_f64(settings.shutdown_grace),
+            term_grace: Duration::from_secs_f64(settings.term_grace),
+        };
+        if let Ok(value) = std::env::var("ALAS_LAUNCHER_SHUTDOWN_GRACE") {
+            match parse_grace(&value) {
+                Some(grace) => policy.grace = grace,
+                None => warn!("Invalid ALAS_LAUNCHER_SHUTDOWN_GRACE {value:?}, using settings"),
+            }
+        }
+        policy
+    }
+}
+
+// Seconds, negative or invalid values are rejected and large ones clamped
+fn parse_grace(value: &str) -> Option<Duration> {
+    value
+        .parse::<f64>()
+        .ok()
+        .filter(|secs| *secs >= 0.0)
+        .and_then(|secs| Duration::try_from_secs_f64(secs.min(MAX_GRACE_SECS)).ok())
+}
+
+type LineListener = Box<dyn Fn(&str) + Send + Sync>;
+
+/// Recent output lines of gui.py, shared across restarts.
+#[derive(Default)]
+pub struct BackendLog {
+    lines: Mutex<VecDeque<String>>,
+    listeners: Mutex<Vec<LineListener>>,
+}
+
+impl BackendLog {
+    const CAPACITY: usize = 1000;
+
+    fn push(&self, line: String) {
+        for listener in self.listeners.lock().unwrap().iter() {
+            listener(&line);
+        }
+        let mut lines = self.lines.lock().unwrap();
+        if lines.len() == Self::CAPACITY {
+            lines.pop_front();
+        }
+        lines.push_back(line);
+    }
+
+    /// Calls `listener` with every new line.
+    pub fn subscribe(&self, listener: impl Fn(&str) + Send + Sync + 'static) {
+        self.listeners.lock().unwrap().push(Box::new(listener));
+    }
+
+    pub fn tail(&self, n: usize) -> Vec<String> {
+        let lines = self.lines.lock().unwrap();
+        lines
+            .iter()
+            .skip(lines.len().saturating_sub(n))
+            .cloned()
+            .collect()
+    }
+}
+
+/// ALAS instances gui.py is running, as far as its output tells.
+///
+/// Instances started from the WebUI are only known this way, so restarts can start them again.
+#[derive(Default)]
+pub struct RunningInstances(Mutex<Vec<String>>);
+
+impl RunningInstances {
+    /// Tracks the instance a line of gui.py's output says started or exited.
+    pub fn update(&self, line: &str) {
+        let Some((config, running)) = parse_instance_line(line) else {
+            return;
+        };
+        let mut instances = self.0.lock().unwrap();
+        instances.retain(|c| c != config);
+        if running {
+            instances.push(config.to_owned());
+        }
+    }
+
+    pub fn get(&self) -> Vec<String> {
+        self.0.lock().unwrap().clone()
+    }
+
+    /// Forgets all instances, they went away with gui.py.
+    pub fn clear(&self) {
+        self.0.lock().unwrap().clear();
+    }
+}
+
+// ALAS logs `Start scheduler loop: alas` when an instance starts, and
+// `[alas] exited. Reason: Manual stop` when it stops
+fn parse_instance_line(line: &str) -> Option<(&str, bool)> {
+    if let Some((_, rest)) = line.split_once("Start scheduler loop: ") {
+        let config = rest.split_whitespace().next()?;
+        return Some((config, true));
+    }
+    let (before, _) = line.split_once("] exited. Reason:")?;
+    let (_, config) = before.rsplit_once('[')?;
+    (!config.is_empty() && !config.contains(char::is_whitespace)).then_some((config, false))
+}
 
 pub struct ManagedBackend {
     child: Option<GroupChild>,
+    tree: Arc<Mutex<ProcessTree>>,
+    // Dropping this stops the thread refreshing `tree`
+    _tree_watcher: Sender<()>,
 }
 
 impl ManagedBackend {
-    pub fn new(port: u16) -> Result<Self> {
-        std::env::set_var("ALAS_LAUNCHER_PID", format!("{}", std::process::id()));
-        let child = Command::new("python")
-            .args(["gui.py", "--host", "127.0.0.1", "--port", &port.to_string()])
+    pub fn new(
+        options: &WebuiOptions,
+        run: &[String],
+        log: Arc<BackendLog>,
+        startup_timeout: Duration,
+    ) -> Result<Self> {
+        let mut child = Command::new("python")
+            .arg("gui.py")
+            .args(options.args(run))
+            // Marks gui.py and its descendants as ours, to find them after a crash
+            .env("ALAS_LAUNCHER_PID", std::process::id().to_string())
+            // Launchers of other profiles must leave our processes alone
+            .env("ALAS_LAUNCHER_REPO", std::env::current_dir()?)
+            // Piped output would be block buffered otherwise
+            .env("PYTHONUNBUFFERED", "1")
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
             .group()
             .create_no_window()
             .spawn()?;
-        let res = Self { child: Some(child) };
+        if let Some(stdout) = child.inner().stdout.take() {
+            capture_output(stdout, io::stdout, log.clone());
+        }
+        if let Some(stderr) = child.inner().stderr.take() {
+            capture_output(stderr, io::stderr, log);
+        }
+        let tree = Arc::new(Mutex::new(ProcessTree::new(child.id())));
+        let res = Self {
+            child: Some(child),
+            tree: tree.clone(),
+            _tree_watcher: watch_tree(tree),
+        };
+        wait_for_port(&options.connect_address(), startup_timeout)?;
+        Ok(res)
+    }
+
+    /// Takes over a gui.py left running by a launcher that exited without cleaning up.
+    pub fn adopt(options: &WebuiOptions, leftovers: &[Leftover]) -> Result<Self> {
+        let tree = Arc::new(Mutex::new(ProcessTree::adopt(leftovers)));
+        let res = Self {
+            child: None,
+            tree: tree.clone(),
+            _tree_watcher: watch_tree(tree),
+        };
+        wait_for_port(&options.connect_address(), Duration::from_secs(5))?;
+        Ok(res)
+    }
 
-        let address = format!("127.0.0.1:{}", port).parse().unwrap();
-        let start_time = std::time::Instant::now();
-        while start_time.elapsed() < Duration::from_secs(60) {
-            if TcpStream::connect_timeout(&address, Duration::from_millis(100)).is_ok() {
-                return Ok(res);
+    /// Pid of gui.py, or of the topmost adopted process.
+    pub fn pid(&self) -> Option<u32> {
+        match &self.child {
+            Some(child) => Some(child.id()),
+            None => self.tree.lock().unwrap().root(),
+        }
+    }
+
+    /// Exit status of gui.py once it exited on its own, never known for adopted processes.
+    pub fn try_wait(&mut self) -> Result<Option<ExitStatus>> {
+        match &mut self.child {
+            Some(child) => Ok(child.try_wait()?),
+            None => Ok(None),
+        }
+    }
+
+    pub fn terminate(
+        &mut self,
+        policy: &ShutdownPolicy,
+        mut status_updater: impl FnMut(&str),
+    ) -> Result<ExitStatus> {
+        let exit_status = match self.child.take() {
+            Some(mut child) => {
+                self.tree.lock().unwrap().refresh();
+                stop_child(&mut child, policy, &mut status_updater)
             }
-            sleep(Duration::from_millis(100));
+            None => stop_adopted(&self.tree, policy, status_updater),
+        };
+        self.kill_leftovers();
+        exit_status
+    }
+
+    fn kill_leftovers(&mut self) {
+        for process in self.tree.lock().unwrap().kill_all() {
+            warn!("Killed leftover process {process}");
         }
-        Err(anyhow!("Timeout waiting for port {} to be ready", port))
     }
+}
 
-    pub fn terminate(&mut self) -> Result<ExitStatus> {
-        if let Some(mut child) = self.child.take() {
-            #[cfg(unix)]
-            {
-                use command_group::{Signal, UnixChildExt};
-                let _ = child.signal(Signal::SIGTERM);
-                let start_time = std::time::Instant::now();
-                while start_time.elapsed() < Duration::from_millis(500) {
-                    if let Ok(Some(exit_status)) = child.try_wait() {
-                        return Ok(exit_status);
-                    }
-                    sleep(Duration::from_millis(100));
+/// Copies gui.py output to our own stdout/stderr, keeping the lines in `log`.
+fn capture_output<W: Write + 'static>(
+    read: impl Read + Send + 'static,
+    out: fn() -> W,
+    log: Arc<BackendLog>,
+) {
+    thread::spawn(move || {
+        let mut reader = BufReader::new(read);
+        let mut buffer = Vec::new();
+        loop {
+            buffer.clear();
+            match reader.read_until(b'\n', &mut buffer) {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {
+                    let _ = out().write_all(&buffer);
+                    let line = String::from_utf8_lossy(&buffer);
+                    log.push(line.trim_end_matches(['\r', '\n']).to_owned());
                 }
-                warn!("gui.py didn't exit, killing it...");
             }
-            child.kill()?;
-            Ok(child.wait()?)
-        } else {
-            Ok(ExitStatus::default())
+        }
+    });
+}
+
+pub fn wait_for_port(address: &str, timeout: Duration) -> Result<()> {
+    let address = address
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| anyhow!("Cannot resolve {}", address))?;
+    let start_time = Instant::now();
+    while start_time.elapsed() < timeout {
+        if TcpStream::connect_timeout(&address, Duration::from_millis(100)).is_ok() {
+            return Ok(());
+        }
+        sleep(Duration::from_millis(100));
+    }
+    Err(anyhow!("Timeout waiting for {} to be ready", address))
+}
+
+fn watch_tree(tree: Arc<Mutex<ProcessTree>>) -> Sender<()> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        while let Err(RecvTimeoutError::Timeout) = rx.recv_timeout(Duration::from_secs(2)) {
+            tree.lock().unwrap().refresh();
+        }
+    });
+    tx
+}
+
+/// What asks gui.py to exit at one step of the shutdown ladder.
+#[derive(Clone, Copy)]
+enum Interrupt {
+    #[cfg(unix)]
+    Signal(nix::sys::signal::Signal),
+    #[cfg(windows)]
+    CtrlBreak,
+}
+
+impl Interrupt {
+    /// Each step of the ladder before killing, with how long gui.py gets to exit after it.
+    fn ladder(policy: &ShutdownPolicy) -> Vec<(Self, Duration)> {
+        #[cfg(unix)]
+        {
+            use nix::sys::signal::Signal;
+            vec![
+                (Self::Signal(Signal::SIGINT), policy.grace),
+                (Self::Signal(Signal::SIGTERM), policy.term_grace),
+            ]
+        }
+        // There's no SIGTERM on Windows, so only one step before the processes are killed
+        #[cfg(windows)]
+        {
+            vec![(Self::CtrlBreak, policy.grace)]
+        }
+    }
+
+    /// Sends the interrupt to the process group gui.py leads.
+    fn send(self, group: u32) -> io::Result<()> {
+        match self {
+            #[cfg(unix)]
+            Self::Signal(signal) => {
+                use nix::{sys::signal::killpg, unistd::Pid};
+                Ok(killpg(Pid::from_raw(group as i32), signal)?)
+            }
+            #[cfg(windows)]
+            Self::CtrlBreak => crate::window_util::send_ctrl_break(group),
+        }
+    }
+}
+
+impl std::fmt::Display for Interrupt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            #[cfg(unix)]
+            Self::Signal(signal) => write!(f, "{signal}"),
+            #[cfg(windows)]
+            Self::CtrlBreak => write!(f, "Ctrl-Break"),
         }
     }
 }
 
+/// Runs the shutdown ladder on the process group `group`, returning the exit status once
+/// `try_wait` sees gui.py exit, or `None` if it's still running and has to be killed.
+fn interrupt(
+    group: u32,
+    policy: &ShutdownPolicy,
+    mut try_wait: impl FnMut() -> Result<Option<ExitStatus>>,
+    mut status_updater: impl FnMut(&str),
+) -> Result<Option<ExitStatus>> {
+    for (interrupt, grace) in Interrupt::ladder(policy) {
+        info!("Sending {interrupt} to gui.py");
+        if let Err(e) = interrupt.send(group) {
+            // Likely gone already, or never was a group leader, no use waiting for it
+            warn!("Failed to send {interrupt} to gui.py: {e}");
+            continue;
+        }
+        let waited = wait_timeout(&mut try_wait, grace, |secs_left| {
+            status_updater(&format!("Waiting for ALAS to exit ({secs_left}s)"))
+        })?;
+        if waited.is_some() {
+            return Ok(waited);
+        }
+        warn!("gui.py didn't exit after {interrupt}");
+    }
+    Ok(None)
+}
+
+fn stop_child(
+    child: &mut GroupChild,
+    policy: &ShutdownPolicy,
+    mut status_updater: impl FnMut(&str),
+) -> Result<ExitStatus> {
+    // gui.py leads its own process group
+    let group = child.id();
+    let waited = interrupt(group, policy, || Ok(child.try_wait()?), &mut status_updater)?;
+    if let Some(exit_status) = waited {
+        return Ok(exit_status);
+    }
+    warn!("Killing gui.py...");
+    status_updater("Killing ALAS");
+    child.kill()?;
+    Ok(child.wait()?)
+}
+
+/// Runs the shutdown ladder on an adopted gui.py, which isn't our child so its exit status
+/// is never known. Whatever is still running afterwards is left to be killed.
+fn stop_adopted(
+    tree: &Mutex<ProcessTree>,
+    policy: &ShutdownPolicy,
+    status_updater: impl FnMut(&str),
+) -> Result<ExitStatus> {
+    let Some(root) = tree.lock().unwrap().root() else {
+        return Ok(ExitStatus::default());
+    };
+    let try_wait = || {
+        let alive = tree.lock().unwrap().is_alive(root);
+        Ok((!alive).then(ExitStatus::default))
+    };
+    let waited = interrupt(root, policy, try_wait, status_updater)?;
+    Ok(waited.unwrap_or_default())
+}
+
+/// Stops processes left by a launcher that exited without cleaning up, the same way as gui.py
+/// itself, returning "pid (name)" of each one that had to be killed.
+pub fn stop_leftovers(
+    leftovers: &[Leftover],
+    policy: &ShutdownPolicy,
+    status_updater: impl FnMut(&str),
+) -> Vec<String> {
+    let tree = Mutex::new(ProcessTree::adopt(leftovers));
+    if let Err(e) = stop_adopted(&tree, policy, status_updater) {
+        warn!("Failed to stop leftover processes: {e}");
+    }
+    tree.into_inner().unwrap().kill_all()
+}
+
+fn wait_timeout(
+    mut try_wait: impl FnMut() -> Result<Option<ExitStatus>>,
+    timeout: Duration,
+    mut on_tick: impl FnMut(u64),
+) -> Result<Option<ExitStatus>> {
+    let start_time = Instant::now();
+    let mut last_secs_left = None;
+    while start_time.elapsed() < timeout {
+        if let Some(exit_status) = try_wait()? {
+            return Ok(Some(exit_status));
+        }
+        let secs_left = timeout.saturating_sub(start_time.elapsed()).as_secs() + 1;
+        if last_secs_left != Some(secs_left) {
+            last_secs_left = Some(secs_left);
+            on_tick(secs_left);
+        }
+        sleep(Duration::from_millis(100));
+    }
+    Ok(None)
+}
+
 impl Drop for ManagedBackend {
     fn drop(&mut self) {
         if let Some(mut child) = self.child.take() {
@@ -67,17 +437,82 @@ impl Drop for ManagedBackend {
                 Err(e) => warn!("Failed to kill gui.py process: {:?}", e),
             }
         }
-        // Kill potential leaked processes
-        let sys = sysinfo::System::new_all();
-        for (pid, process) in sys.processes() {
-            for var in process.environ() {
-                if pid.as_u32() != std::process::id()
-                    && var.to_str().unwrap_or_default()
-                        == format!("ALAS_LAUNCHER_PID={}", std::process::id())
-                {
-                    process.kill();
-                }
-            }
+        self.kill_leftovers();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_running_instances() {
+        let instances = RunningInstances::default();
+        for line in [
+            "2024-05-01 12:00:00.000 | INFO | Start scheduler loop: alas",
+            "2024-05-01 12:00:00.000 | INFO | Start scheduler loop: alas2",
+            "2024-05-01 12:00:01.000 | INFO | Scheduler: Start task `Commission`",
+            "2024-05-01 12:00:00.000 | INFO | Start scheduler loop: alas",
+        ] {
+            instances.update(line);
         }
+        assert_eq!(vec!["alas2", "alas"], instances.get());
+        instances.update("2024-05-01 12:30:00.000 | INFO | [alas2] exited. Reason: Manual stop");
+        instances.update("[not an instance] exited. Reason: Finish");
+        assert_eq!(vec!["alas"], instances.get());
+        instances.clear();
+        assert!(instances.get().is_empty());
+    }
+
+    #[test]
+    fn test_parse_grace() {
+        assert_eq!(Some(Duration::from_millis(2500)), parse_grace("2.5"));
+        assert_eq!(Some(Duration::ZERO), parse_grace("0"));
+        let max = Duration::from_secs_f64(MAX_GRACE_SECS);
+        assert_eq!(Some(max), parse_grace("1e9"));
+        assert_eq!(Some(max), parse_grace("inf"));
+        assert_eq!(None, parse_grace("-1"));
+        assert_eq!(None, parse_grace("NaN"));
+        assert_eq!(None, parse_grace("10s"));
+        assert_eq!(None, parse_grace(""));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_shutdown_ladder() {
+        use nix::sys::signal::Signal;
+        use std::os::unix::process::ExitStatusExt;
+
+        let policy = ShutdownPolicy {
+            grace: Duration::from_millis(300),
+            term_grace: Duration::from_millis(300),
+        };
+        let stop = |script: &str| {
+            let mut child = Command::new("sh")
+                .args(["-c", script])
+                .group_spawn()
+                .unwrap();
+            // Give the shell time to set its traps
+            sleep(Duration::from_millis(200));
+            let start = Instant::now();
+            let status = stop_child(&mut child, &policy, |_| {}).unwrap();
+            (status.signal(), start.elapsed())
+        };
+
+        // Exits on SIGINT right away
+        let (signal, elapsed) = stop("sleep 30");
+        assert_eq!(Some(Signal::SIGINT as i32), signal);
+        assert!(elapsed < policy.grace, "{elapsed:?}");
+
+        // Ignores SIGINT, so it gets SIGTERM once the grace period is over
+        let (signal, elapsed) = stop(r#"trap "" INT; sleep 30"#);
+        assert_eq!(Some(Signal::SIGTERM as i32), signal);
+        assert!(elapsed >= policy.grace, "{elapsed:?}");
+        assert!(elapsed < policy.grace + policy.term_grace, "{elapsed:?}");
+
+        // Ignores both, so it's killed after both grace periods
+        let (signal, elapsed) = stop(r#"trap "" INT TERM; sleep 30"#);
+        assert_eq!(Some(Signal::SIGKILL as i32), signal);
+        assert!(elapsed >= policy.grace + policy.term_grace, "{elapsed:?}");
     }
 }
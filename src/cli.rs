@@ -0,0 +1,344 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+pub const USAGE: &str = "\
+Usage: alas-launcher [--profile NAME] [--minimized | --headless] [--remote URL] [ACTION]
+       alas-launcher [--profile NAME] ctl [--json] COMMAND
+       alas-launcher [--profile NAME] autostart enable [--minimized] | disable | status
+       alas-launcher [--profile NAME] install-service | uninstall-service
+
+  --profile NAME      Use a profile from profiles.yaml in the launcher config folder,
+                      a picker is shown at startup if there are several
+  --minimized         Start without showing the window, in the tray if it's enabled
+  --headless          Run ALAS without any window until stopped, e.g. as a service
+  --remote URL        Connect to an ALAS WebUI running elsewhere instead of starting one,
+                      its password is read from ALAS_REMOTE_PASSWORD
+
+Actions, forwarded to the running launcher if there is one:
+  (none), show        Show the launcher window
+  --run CONFIG...     Restart ALAS running the given configs
+  restart             Restart ALAS
+  update              Update ALAS and restart it
+  logs                Print recent ALAS output
+
+Commands for the running launcher, failing if there is none:
+  status              Print the launcher status
+  show                Show the launcher window
+  run CONFIG...       Restart ALAS running the given configs
+  restart             Restart ALAS
+  update              Update ALAS and restart it
+  stop                Stop ALAS, leaving the launcher open
+  logs [-n LINES]     Print the last LINES of ALAS output, 100 by default
+  health              Print what the watchdog saw recently
+
+  --json              Print the raw JSON reply
+
+Start at login, for the given profile:
+  autostart enable    Start the launcher with the user session, --minimized to keep it hidden
+  autostart disable   Don't start it at login anymore
+  autostart status    Print whether it starts at login
+
+Service on Linux, for the given profile:
+  install-service     Install and enable a systemd user service running the launcher headless
+  uninstall-service   Stop, disable and remove the service";
+
+/// What an invocation asks for, either at startup or from the running launcher.
+///
+/// This is also the command set of the control endpoint, e.g. `{"command": "logs", "lines": 20}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum Action {
+    Show,
+    Run { configs: Vec<String> },
+    Status,
+    Restart,
+    Update,
+    Stop,
+    Logs { lines: usize },
+    Health,
+}
+
+pub enum Invocation {
+    /// Start the launcher, or forward the action if it's already running
+    Launch {
+        action: Action,
+        profile: Option<String>,
+        /// URL of a remote WebUI to connect to
+        remote: Option<String>,
+        /// Don't show the window at startup
+        minimized: bool,
+        /// Don't create any window
+        headless: bool,
+    },
+    /// Send a command to the running launcher
+    Ctl {
+        action: Action,
+        profile: Option<String>,
+        json: bool,
+    },
+    /// Change whether the launcher starts at login
+    Autostart {
+        command: AutostartCommand,
+        profile: Option<String>,
+    },
+    /// Install or remove the systemd user service
+    Service {
+        command: ServiceCommand,
+        profile: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ServiceCommand {
+    Install,
+    Uninstall,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AutostartCommand {
+    Enable { minimized: bool },
+    Disable,
+    Status,
+}
+
+/// Parses command line arguments, without the program name.
+pub fn parse_args(args: &[String]) -> Result<Invocation> {
+    let (profile, args) = match args.split_first() {
+        Some((first, rest)) if first == "--profile" => {
+            let (name, rest) = rest
+                .split_first()
+                .ok_or_else(|| anyhow!("Missing name after --profile"))?;
+            (Some(name.clone()), rest)
+        }
+        _ => (None, args),
+    };
+    match args.split_first() {
+        Some((first, rest)) if first == "ctl" => {
+            let (json, rest) = match rest.split_first() {
+                Some((flag, rest)) if flag == "--json" => (true, rest),
+                _ => (false, rest),
+            };
+            let action = parse_command(rest)?;
+            Ok(Invocation::Ctl {
+                action,
+                profile,
+                json,
+            })
+        }
+        Some((first, rest)) if first == "autostart" => {
+            let rest = rest.iter().map(String::as_str).collect::<Vec<_>>();
+            let command = match rest.as_slice() {
+                ["enable"] => AutostartCommand::Enable { minimized: false },
+                ["enable", "--minimized"] => AutostartCommand::Enable { minimized: true },
+                ["disable"] => AutostartCommand::Disable,
+                ["status"] => AutostartCommand::Status,
+                _ => return Err(anyhow!("Expected autostart enable, disable or status")),
+            };
+            Ok(Invocation::Autostart { command, profile })
+        }
+        Some((first, [])) if first == "install-service" => Ok(Invocation::Service {
+            command: ServiceCommand::Install,
+            profile,
+        }),
+        Some((first, [])) if first == "uninstall-service" => Ok(Invocation::Service {
+            command: ServiceCommand::Uninstall,
+            profile,
+        }),
+        _ => parse_launch(args, profile),
+    }
+}
+
+fn parse_launch(mut args: &[String], profile: Option<String>) -> Result<Invocation> {
+    let (mut minimized, mut headless) = (false, false);
+    while let Some((first, rest)) = args.split_first() {
+        match first.as_str() {
+            "--minimized" => minimized = true,
+            "--headless" => headless = true,
+            _ => break,
+        }
+        args = rest;
+    }
+    if minimized && headless {
+        return Err(anyhow!("--minimized and --headless can't be used together"));
+    }
+    let (remote, args) = match args.split_first() {
+        Some((first, rest)) if first == "--remote" => {
+            let (url, rest) = rest
+                .split_first()
+                .ok_or_else(|| anyhow!("Missing URL after --remote"))?;
+            (Some(url.clone()), rest)
+        }
+        _ => (None, args),
+    };
+    Ok(Invocation::Launch {
+        action: parse_action(args)?,
+        profile,
+        remote,
+        minimized,
+        headless,
+    })
+}
+
+/// Parses the arguments of a plain invocation, which are also what a second instance forwards.
+pub fn parse_action(args: &[String]) -> Result<Action> {
+    let Some((first, rest)) = args.split_first() else {
+        return Ok(Action::Show);
+    };
+    match (first.as_str(), rest) {
+        ("show", []) => Ok(Action::Show),
+        ("--run" | "run", configs) if !configs.is_empty() => Ok(Action::Run {
+            configs: configs.to_vec(),
+        }),
+        ("restart", []) => Ok(Action::Restart),
+        ("update", []) => Ok(Action::Update),
+        ("logs", []) => Ok(Action::Logs { lines: 100 }),
+        _ => Err(anyhow!("Unrecognized arguments: {}", args.join(" "))),
+    }
+}
+
+fn parse_command(args: &[String]) -> Result<Action> {
+    let args_str = args.iter().map(String::as_str).collect::<Vec<_>>();
+    match args_str.as_slice() {
+        ["status"] => Ok(Action::Status),
+        ["stop"] => Ok(Action::Stop),
+        ["health"] => Ok(Action::Health),
+        ["logs", "-n", lines] => {
+            let lines = lines
+                .parse()
+                .map_err(|_| anyhow!("Invalid number of lines: {lines}"))?;
+            Ok(Action::Logs { lines })
+        }
+        [] => Err(anyhow!("Missing command")),
+        _ => parse_action(args),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn parse(a: &[&str]) -> Option<Action> {
+        parse_action(&args(a)).ok()
+    }
+
+    fn parse_ctl(a: &[&str]) -> Option<(Action, bool)> {
+        match parse_args(&args(a)).ok()? {
+            Invocation::Ctl { action, json, .. } => Some((action, json)),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_parse_action() {
+        assert_eq!(Some(Action::Show), parse(&[]));
+        assert_eq!(Some(Action::Restart), parse(&["restart"]));
+        assert_eq!(
+            Some(Action::Run {
+                configs: args(&["alas", "alas2"])
+            }),
+            parse(&["--run", "alas", "alas2"])
+        );
+        assert_eq!(None, parse(&["--run"]));
+        assert_eq!(None, parse(&["restart", "now"]));
+        assert_eq!(None, parse(&["--bogus"]));
+        assert_eq!(None, parse(&["stop"]));
+    }
+
+    #[test]
+    fn test_parse_ctl() {
+        assert_eq!(Some((Action::Status, false)), parse_ctl(&["ctl", "status"]));
+        assert_eq!(
+            Some((Action::Stop, true)),
+            parse_ctl(&["ctl", "--json", "stop"])
+        );
+        assert_eq!(
+            Some((Action::Logs { lines: 20 }, false)),
+            parse_ctl(&["ctl", "logs", "-n", "20"])
+        );
+        assert_eq!(
+            Some((Action::Logs { lines: 100 }, false)),
+            parse_ctl(&["ctl", "logs"])
+        );
+        assert_eq!(Some((Action::Health, false)), parse_ctl(&["ctl", "health"]));
+        assert_eq!(None, parse_ctl(&["ctl"]));
+        assert_eq!(None, parse_ctl(&["ctl", "logs", "-n", "many"]));
+    }
+
+    #[test]
+    fn test_parse_remote() {
+        let Ok(Invocation::Launch { action, remote, .. }) =
+            parse_args(&args(&["--remote", "http://server:22267/", "show"]))
+        else {
+            panic!("not a launch");
+        };
+        assert_eq!(Action::Show, action);
+        assert_eq!(Some("http://server:22267/".to_owned()), remote);
+        assert!(parse_args(&args(&["--remote"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_profile() {
+        let Ok(Invocation::Ctl {
+            action, profile, ..
+        }) = parse_args(&args(&["--profile", "dev", "ctl", "status"]))
+        else {
+            panic!("not a ctl");
+        };
+        assert_eq!(Action::Status, action);
+        assert_eq!(Some("dev".to_owned()), profile);
+        let Ok(Invocation::Launch {
+            action, profile, ..
+        }) = parse_args(&args(&["--profile", "dev"]))
+        else {
+            panic!("not a launch");
+        };
+        assert_eq!(Action::Show, action);
+        assert_eq!(Some("dev".to_owned()), profile);
+        assert!(parse_args(&args(&["--profile"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_autostart() {
+        let Ok(Invocation::Autostart { command, profile }) = parse_args(&args(&[
+            "--profile",
+            "dev",
+            "autostart",
+            "enable",
+            "--minimized",
+        ])) else {
+            panic!("not an autostart");
+        };
+        assert_eq!(AutostartCommand::Enable { minimized: true }, command);
+        assert_eq!(Some("dev".to_owned()), profile);
+        assert!(parse_args(&args(&["autostart"])).is_err());
+        assert!(parse_args(&args(&["autostart", "disable", "--minimized"])).is_err());
+        let Ok(Invocation::Launch {
+            action,
+            minimized,
+            remote,
+            ..
+        }) = parse_args(&args(&["--minimized", "--remote", "http://server/"]))
+        else {
+            panic!("not a launch");
+        };
+        assert_eq!(Action::Show, action);
+        assert!(minimized);
+        assert_eq!(Some("http://server/".to_owned()), remote);
+        assert!(parse_args(&args(&["--minimized", "--headless"])).is_err());
+        assert!(matches!(
+            parse_args(&args(&["--headless"])),
+            Ok(Invocation::Launch { headless: true, .. })
+        ));
+        assert!(matches!(
+            parse_args(&args(&["install-service"])),
+            Ok(Invocation::Service {
+                command: ServiceCommand::Install,
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,250 @@
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    path::{Path, PathBuf},
+    thread,
+};
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::{cli::Action, host::Host};
+
+/// One line of JSON sent to the control endpoint.
+#[derive(Serialize, Deserialize)]
+struct Request {
+    /// Only checked on the loopback TCP endpoint, which other local users could reach
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    token: Option<String>,
+    #[serde(flatten)]
+    action: Action,
+}
+
+/// One line of JSON sent back by the control endpoint.
+#[derive(Serialize, Deserialize)]
+pub struct Reply {
+    pub ok: bool,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
+}
+
+impl Reply {
+    fn from_result(result: Result<Reply>) -> Self {
+        result.unwrap_or_else(|e| Reply {
+            ok: false,
+            message: e.to_string(),
+            data: None,
+        })
+    }
+
+    fn message(message: impl Into<String>) -> Self {
+        Self {
+            ok: true,
+            message: message.into(),
+            data: None,
+        }
+    }
+}
+
+// A Unix socket, or a file holding the loopback port and token to connect with on Windows.
+// Each profile has its own launcher, and so its own channel.
+fn channel_path(profile: Option<&str>) -> PathBuf {
+    let dir = dirs::runtime_dir().unwrap_or_else(std::env::temp_dir);
+    let name = match profile {
+        Some(profile) => format!("alas-launcher-{profile}"),
+        None => "alas-launcher".to_owned(),
+    };
+    if cfg!(unix) {
+        dir.join(format!("{name}.sock"))
+    } else {
+        dir.join(format!("{name}.port"))
+    }
+}
+
+/// Sends `action` to the launcher running `profile` and returns its reply.
+/// Returns `None` if no such launcher is running.
+pub fn forward(profile: Option<&str>, action: &Action) -> Option<Result<Reply>> {
+    let (stream, token) = connect(&channel_path(profile)).ok()?;
+    Some(request(stream, token, action))
+}
+
+fn request(mut stream: impl Read + Write, token: Option<String>, action: &Action) -> Result<Reply> {
+    let mut request = serde_json::to_string(&Request {
+        token,
+        action: action.clone(),
+    })?;
+    request.push('\n');
+    stream.write_all(request.as_bytes())?;
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line)?;
+    Ok(serde_json::from_str(&line)?)
+}
+
+#[cfg(unix)]
+fn connect(path: &Path) -> std::io::Result<(impl Read + Write, Option<String>)> {
+    let stream = std::os::unix::net::UnixStream::connect(path)?;
+    Ok((stream, None))
+}
+
+#[cfg(not(unix))]
+fn connect(path: &Path) -> std::io::Result<(impl Read + Write, Option<String>)> {
+    let content = std::fs::read_to_string(path)?;
+    let mut lines = content.lines();
+    let port = lines
+        .next()
+        .and_then(|port| port.trim().parse::<u16>().ok())
+        .ok_or_else(|| std::io::Error::other("Invalid control channel file"))?;
+    let token = lines.next().map(|token| token.trim().to_owned());
+    let stream = std::net::TcpStream::connect(("127.0.0.1", port))?;
+    Ok((stream, token))
+}
+
+/// Starts the control endpoint, must only be called by the single running instance.
+#[cfg(unix)]
+pub fn listen(host: impl Host, profile: Option<&str>) -> Result<()> {
+    use std::os::unix::{fs::PermissionsExt, net::UnixListener};
+    let path = channel_path(profile);
+    // Only a socket left over from a launcher that didn't exit cleanly refuses connections,
+    // another launcher of this profile starting at the same time must keep its own
+    match std::os::unix::net::UnixStream::connect(&path) {
+        Ok(_) => {
+            return Err(anyhow!(
+                "Another launcher is listening on {}",
+                path.display()
+            ))
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::ConnectionRefused => {
+            let _ = std::fs::remove_file(&path);
+        }
+        Err(_) => {}
+    }
+    let listener = UnixListener::bind(&path)?;
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let host = host.clone();
+            thread::spawn(move || serve(&host, stream, None));
+        }
+    });
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn listen(host: impl Host, profile: Option<&str>) -> Result<()> {
+    let path = channel_path(profile);
+    if connect(&path).is_ok() {
+        return Err(anyhow!(
+            "Another launcher is listening on {}",
+            path.display()
+        ));
+    }
+    let listener = std::net::TcpListener::bind(("127.0.0.1", 0))?;
+    let token = new_token();
+    std::fs::write(
+        path,
+        format!("{}\n{}\n", listener.local_addr()?.port(), token),
+    )?;
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let host = host.clone();
+            let token = token.clone();
+            thread::spawn(move || serve(&host, stream, Some(&token)));
+        }
+    });
+    Ok(())
+}
+
+// Random enough for a file only readable by the current user, without another dependency
+#[cfg(not(unix))]
+fn new_token() -> String {
+    use std::hash::{BuildHasher, RandomState};
+    (0..4)
+        .map(|i| format!("{:016x}", RandomState::new().hash_one(i)))
+        .collect()
+}
+
+fn serve(host: &impl Host, mut stream: impl Read + Write, token: Option<&str>) {
+    let mut line = String::new();
+    if let Err(e) = BufReader::new(&mut stream).read_line(&mut line) {
+        warn!("Failed to read control request: {e}");
+        return;
+    }
+    let result = serde_json::from_str::<Request>(&line)
+        .map_err(anyhow::Error::from)
+        .and_then(|request| {
+            if token.is_some() && request.token.as_deref() != token {
+                return Err(anyhow!("Invalid control token"));
+            }
+            info!("Received control request {:?}", request.action);
+            execute(host, request.action)
+        });
+    let mut reply = serde_json::to_string(&Reply::from_result(result)).unwrap();
+    reply.push('\n');
+    let _ = stream.write_all(reply.as_bytes());
+}
+
+/// Runs an action in this launcher, blocking until it's done.
+pub fn execute(host: &impl Host, action: Action) -> Result<Reply> {
+    let launcher = host.launcher();
+    match action {
+        Action::Show => {
+            host.show()?;
+            Ok(Reply::message("Launcher window shown"))
+        }
+        Action::Run { configs } => {
+            launcher.add_run(&configs);
+            host.restart(false)?;
+            Ok(Reply::message(format!(
+                "ALAS restarted running {}",
+                configs.join(", ")
+            )))
+        }
+        Action::Status => {
+            let status = launcher.status();
+            let message = format!("ALAS: {}", status.state);
+            Ok(Reply {
+                ok: true,
+                message,
+                data: Some(serde_json::to_value(status)?),
+            })
+        }
+        Action::Restart => {
+            host.restart(false)?;
+            Ok(Reply::message("ALAS restarted"))
+        }
+        Action::Update => {
+            host.restart(true)?;
+            Ok(Reply::message("ALAS updated and restarted"))
+        }
+        Action::Stop => {
+            host.stop()?;
+            Ok(Reply::message("ALAS stopped"))
+        }
+        Action::Logs { lines } => {
+            let lines = launcher.log().tail(lines);
+            Ok(Reply {
+                ok: true,
+                message: lines.join("\n"),
+                data: Some(serde_json::to_value(lines)?),
+            })
+        }
+        Action::Health => {
+            let events = launcher.health_events();
+            let message = if events.is_empty() {
+                "No health events".to_owned()
+            } else {
+                events
+                    .iter()
+                    .map(|e| format!("{} {:?} {}", e.time, e.kind, e.detail))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            };
+            Ok(Reply {
+                ok: true,
+                message,
+                data: Some(serde_json::to_value(events)?),
+            })
+        }
+    }
+}
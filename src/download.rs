@@ -0,0 +1,171 @@
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        LazyLock, Mutex,
+    },
+};
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use tauri::{webview::DownloadEvent, Url, Webview};
+use tauri_plugin_dialog::{DialogExt, FilePath};
+use tracing::{info, warn};
+
+/// Downloads in progress, by URL. macOS doesn't tell where a finished download went.
+static DOWNLOADS: LazyLock<Mutex<HashMap<Url, PathBuf>>> = LazyLock::new(Default::default);
+static NEXT_ID: AtomicU32 = AtomicU32::new(0);
+
+/// What happened to a download or `save_as` export, dispatched to the page as an
+/// `alas-launcher-download` event.
+#[derive(Debug, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum DownloadResult {
+    Saved { filename: String, path: PathBuf },
+    Cancelled { filename: String },
+    Failed { filename: String, error: String },
+}
+
+/// Lets the webview download into a temp folder, then asks where to keep the file.
+///
+/// Works for anything the WebUI downloads: `<a download>` links, blob URLs and file responses.
+pub fn handle(webview: Webview, event: DownloadEvent<'_>) -> bool {
+    match event {
+        DownloadEvent::Requested { url, destination } => {
+            let filename = file_name(destination, &url);
+            let temp = match temp_path(&filename) {
+                Ok(temp) => temp,
+                Err(e) => {
+                    warn!("Failed to prepare download of {filename}: {e}");
+                    report(
+                        &webview,
+                        DownloadResult::Failed {
+                            filename,
+                            error: e.to_string(),
+                        },
+                    );
+                    return false;
+                }
+            };
+            info!("Downloading {url} to {}", temp.display());
+            *destination = temp.clone();
+            DOWNLOADS.lock().unwrap().insert(url, temp);
+            true
+        }
+        DownloadEvent::Finished { url, path, success } => {
+            let temp = DOWNLOADS.lock().unwrap().remove(&url);
+            let Some(temp) = path.or(temp) else {
+                warn!("Finished download of {url} was never requested");
+                return true;
+            };
+            let filename = temp.file_name().map_or_else(
+                || "download".to_owned(),
+                |name| name.to_string_lossy().into_owned(),
+            );
+            if !success {
+                warn!("Download of {url} failed");
+                remove_temp(&temp);
+                report(
+                    &webview,
+                    DownloadResult::Failed {
+                        filename,
+                        error: "Download failed".to_owned(),
+                    },
+                );
+                return true;
+            }
+            let handle = webview.clone();
+            webview
+                .dialog()
+                .file()
+                .set_file_name(&filename)
+                .save_file(move |path| {
+                    let result = match path {
+                        None => {
+                            remove_temp(&temp);
+                            DownloadResult::Cancelled { filename }
+                        }
+                        Some(path) => match keep(&temp, &path) {
+                            Ok(path) => {
+                                info!("Saved download to {}", path.display());
+                                DownloadResult::Saved { filename, path }
+                            }
+                            Err(e) => {
+                                warn!("Failed to save download: {e}");
+                                remove_temp(&temp);
+                                DownloadResult::Failed {
+                                    filename,
+                                    error: e.to_string(),
+                                }
+                            }
+                        },
+                    };
+                    report(&handle, result);
+                });
+            true
+        }
+        _ => true,
+    }
+}
+
+/// Name the webview suggested, or the last part of the URL.
+fn file_name(suggested: &Path, url: &Url) -> String {
+    suggested
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .or_else(|| {
+            url.path_segments()?
+                .next_back()
+                .filter(|name| !matches!(*name, "" | "." | ".."))
+                .map(str::to_owned)
+        })
+        .unwrap_or_else(|| "download".to_owned())
+}
+
+/// A fresh folder per download, so the file keeps its name.
+fn temp_path(filename: &str) -> Result<PathBuf> {
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    let dir = std::env::temp_dir()
+        .join("alas-launcher-downloads")
+        .join(format!("{}-{id}", std::process::id()));
+    fs::create_dir_all(&dir)?;
+    Ok(dir.join(filename))
+}
+
+fn keep(temp: &Path, path: &FilePath) -> Result<PathBuf> {
+    let path = path
+        .as_path()
+        .ok_or_else(|| anyhow!("Invalid file path {path:?}"))?;
+    if let Err(e) = fs::rename(temp, path) {
+        // The temp folder may be on another file system
+        if e.kind() == io::ErrorKind::NotFound {
+            return Err(e.into());
+        }
+        fs::copy(temp, path)?;
+        remove_temp(temp);
+    } else if let Some(dir) = temp.parent() {
+        let _ = fs::remove_dir(dir);
+    }
+    Ok(path.to_owned())
+}
+
+fn remove_temp(temp: &Path) {
+    let _ = fs::remove_file(temp);
+    if let Some(dir) = temp.parent() {
+        let _ = fs::remove_dir(dir);
+    }
+}
+
+fn report(webview: &Webview, result: DownloadResult) {
+    let Ok(detail) = serde_json::to_string(&result) else {
+        return;
+    };
+    let js = format!(
+        "window.dispatchEvent(new CustomEvent('alas-launcher-download', {{ detail: {detail} }}));"
+    );
+    if let Err(e) = webview.eval(&js) {
+        warn!("Failed to report download to the page: {e}");
+    }
+}
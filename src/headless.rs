@@ -0,0 +1,100 @@
+use std::{
+    sync::{
+        mpsc::{self, RecvTimeoutError},
+        Arc,
+    },
+    time::Duration,
+};
+
+use anyhow::{anyhow, Result};
+use tracing::{info, warn};
+
+use crate::{
+    control, host::Host, launcher::Launcher, log_watch, notify::NotificationKind,
+    process_tree::find_leftovers, scheduler, settings::Settings, state::LauncherState, watchdog,
+};
+
+/// The launcher without any window, progress and alerts only go to the log.
+#[derive(Clone)]
+struct Headless(Arc<Launcher>);
+
+fn log_status(text: &str) {
+    info!("{text}");
+}
+
+impl Host for Headless {
+    fn launcher(&self) -> &Launcher {
+        &self.0
+    }
+
+    fn restart(&self, update: bool) -> Result<()> {
+        self.0.restart(update, log_status)
+    }
+
+    fn stop(&self) -> Result<()> {
+        self.0.stop_backend(log_status)
+    }
+
+    fn show(&self) -> Result<()> {
+        Err(anyhow!(
+            "alas-launcher is running headless, open {} in a browser",
+            self.0.target().url()
+        ))
+    }
+
+    fn notify(&self, _kind: NotificationKind, title: &str, body: &str) {
+        info!("{title}: {body}");
+    }
+
+    // Alerts are logged when they're raised
+    fn set_alert(&self, _alert: &str) {}
+}
+
+/// Runs gui.py without a window until the launcher is asked to stop, e.g. as a systemd service.
+///
+/// Fails if gui.py exits on its own or can't be restarted, so the service manager can restart
+/// the launcher.
+pub fn run(launcher: Launcher, settings: &Settings) -> Result<()> {
+    let host = Headless(Arc::new(launcher));
+    let launcher = host.launcher();
+    let (tx, rx) = mpsc::channel();
+    ctrlc::set_handler(move || {
+        let _ = tx.send(());
+    })?;
+    // Nobody is around to ask, a previous run of the service left them behind
+    if !launcher.is_remote() {
+        for process in launcher.stop_leftovers(&find_leftovers(), log_status) {
+            warn!("Killed leftover process {process}");
+        }
+    }
+    if let Err(e) = control::listen(host.clone(), launcher.profile()) {
+        warn!("Failed to listen for ctl commands: {e}");
+    }
+    log_watch::watch(host.clone(), settings);
+    scheduler::start(host.clone(), &settings.schedule);
+    watchdog::start(host.clone(), &settings.watchdog);
+    let result = launcher.start(log_status).and_then(|()| {
+        info!("ALAS is running on {}", launcher.target().url());
+        loop {
+            match rx.recv_timeout(Duration::from_secs(2)) {
+                Ok(()) | Err(RecvTimeoutError::Disconnected) => {
+                    info!("Received a signal, shutting down...");
+                    return Ok(());
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+            }
+            if let Some(status) = launcher.backend_exited() {
+                return Err(anyhow!("gui.py exited with {status}"));
+            }
+            // A restart from ctl, the scheduler or the watchdog failed
+            if let LauncherState::Failed { reason } = launcher.state().get() {
+                return Err(anyhow!(reason));
+            }
+        }
+    });
+    if let Err(e) = launcher.shutdown(log_status) {
+        warn!("Failed to terminate backend process: {e:?}");
+    }
+    launcher.release_repo();
+    result
+}
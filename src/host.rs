@@ -0,0 +1,58 @@
+use anyhow::Result;
+use tauri::{AppHandle, Manager};
+
+use crate::{
+    launcher::Launcher,
+    notify::{self, NotificationKind},
+    tray,
+};
+
+/// What the launcher runs in, the app with its windows or headless mode.
+///
+/// The control endpoint, the scheduler, the watchdog and the log watcher only go through this,
+/// so they work the same in both.
+pub trait Host: Clone + Send + Sync + 'static {
+    fn launcher(&self) -> &Launcher;
+
+    /// Restarts gui.py, showing progress where the user can see it, blocking until it's done.
+    fn restart(&self, update: bool) -> Result<()>;
+
+    /// Stops gui.py until the next restart, blocking until it's done.
+    fn stop(&self) -> Result<()>;
+
+    /// Brings the launcher window to the front.
+    fn show(&self) -> Result<()>;
+
+    /// Shows a desktop notification, if there is a desktop.
+    fn notify(&self, kind: NotificationKind, title: &str, body: &str);
+
+    /// Flags an alert raised from the output of ALAS until the user looks at it.
+    fn set_alert(&self, alert: &str);
+}
+
+impl Host for AppHandle {
+    fn launcher(&self) -> &Launcher {
+        self.state::<Launcher>().inner()
+    }
+
+    fn restart(&self, update: bool) -> Result<()> {
+        crate::restart_with_progress(self, update)
+    }
+
+    fn stop(&self) -> Result<()> {
+        crate::stop_with_progress(self)
+    }
+
+    fn show(&self) -> Result<()> {
+        crate::show_main_window(self);
+        Ok(())
+    }
+
+    fn notify(&self, kind: NotificationKind, title: &str, body: &str) {
+        notify::send(self, kind, title, body);
+    }
+
+    fn set_alert(&self, alert: &str) {
+        tray::set_alert(self, Some(alert));
+    }
+}
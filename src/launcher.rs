@@ -0,0 +1,373 @@
+use std::{
+    collections::VecDeque,
+    process::ExitStatus,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use tracing::{info, warn};
+
+use crate::{
+    backend::{self, wait_for_port, BackendLog, ManagedBackend, RunningInstances, ShutdownPolicy},
+    process_tree::Leftover,
+    profile::Profile,
+    repo_lock::RepoLock,
+    settings::{Settings, UpdatePolicy},
+    setup::setup_alas_repo,
+    state::{LauncherState, StateCell},
+    watchdog::HealthEvent,
+    webui::{RemoteTarget, Target, WebuiOptions},
+};
+
+/// Health events kept for `ctl health`
+const MAX_HEALTH_EVENTS: usize = 100;
+
+/// Owns the gui.py backend and serializes start / restart / stop requests.
+pub struct Launcher {
+    target: Target,
+    profile: Option<String>,
+    /// Whether ALAS is updated on start, restarts only update when asked to
+    update_on_start: bool,
+    backend: Mutex<Option<ManagedBackend>>,
+    log: Arc<BackendLog>,
+    instances: Arc<RunningInstances>,
+    state: StateCell,
+    /// Configs passed to gui.py's `--run`, on top of those in deploy.yaml
+    run: Mutex<Vec<String>>,
+    repo_lock: Mutex<Option<RepoLock>>,
+    shutdown_policy: ShutdownPolicy,
+    startup_timeout: Duration,
+    busy: AtomicBool,
+    shutting_down: AtomicBool,
+    health: Mutex<VecDeque<HealthEvent>>,
+}
+
+#[derive(Serialize)]
+pub struct LauncherStatus {
+    pub profile: Option<String>,
+    pub running: bool,
+    pub busy: bool,
+    pub port: u16,
+    pub pid: Option<u32>,
+    pub run: Vec<String>,
+    /// ALAS instances gui.py is running
+    pub instances: Vec<String>,
+    pub state: LauncherState,
+}
+
+struct BusyGuard<'a>(&'a AtomicBool);
+
+impl Drop for BusyGuard<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Release);
+    }
+}
+
+impl Launcher {
+    pub fn new(target: Target, profile: Option<&Profile>, settings: &Settings) -> Self {
+        let log = Arc::<BackendLog>::default();
+        let instances = Arc::<RunningInstances>::default();
+        let tracked = instances.clone();
+        log.subscribe(move |line| tracked.update(line));
+        Self {
+            target,
+            profile: profile.map(|p| p.name.clone()),
+            update_on_start: settings.update == UpdatePolicy::OnStart
+                && profile.is_none_or(|p| p.update),
+            backend: Mutex::new(None),
+            log,
+            instances,
+            state: StateCell::default(),
+            run: Mutex::default(),
+            repo_lock: Mutex::new(None),
+            shutdown_policy: ShutdownPolicy::new(settings),
+            startup_timeout: settings.startup_timeout(),
+            busy: AtomicBool::new(false),
+            shutting_down: AtomicBool::new(false),
+            health: Mutex::default(),
+        }
+    }
+
+    pub fn target(&self) -> &Target {
+        &self.target
+    }
+
+    pub fn is_remote(&self) -> bool {
+        matches!(self.target, Target::Remote(_))
+    }
+
+    // Managing gui.py only makes sense for a local target
+    fn local(&self) -> Result<&WebuiOptions> {
+        match &self.target {
+            Target::Local(options) => Ok(options),
+            Target::Remote(remote) => Err(anyhow!(
+                "ALAS at {} is managed on its own machine, this launcher can only connect to it",
+                remote.url
+            )),
+        }
+    }
+
+    pub fn log(&self) -> &BackendLog {
+        &self.log
+    }
+
+    pub fn profile(&self) -> Option<&str> {
+        self.profile.as_deref()
+    }
+
+    pub fn state(&self) -> &StateCell {
+        &self.state
+    }
+
+    /// Records the outcome of a start or restart.
+    fn finish(&self, result: Result<()>) -> Result<()> {
+        if let Err(e) = &result {
+            self.state.set(LauncherState::Failed {
+                reason: e.to_string(),
+            });
+        }
+        result
+    }
+
+    pub fn record_health(&self, event: HealthEvent) {
+        let mut health = self.health.lock().unwrap();
+        if health.len() == MAX_HEALTH_EVENTS {
+            health.pop_front();
+        }
+        health.push_back(event);
+    }
+
+    /// What the watchdog saw recently, oldest first.
+    pub fn health_events(&self) -> Vec<HealthEvent> {
+        self.health.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Adds configs to be run by gui.py, taking effect on the next start.
+    pub fn add_run(&self, configs: &[String]) {
+        let mut run = self.run.lock().unwrap();
+        for config in configs {
+            if !run.contains(config) {
+                run.push(config.clone());
+            }
+        }
+    }
+
+    fn begin(&self) -> Result<BusyGuard<'_>> {
+        if self.busy.swap(true, Ordering::AcqRel) {
+            return Err(anyhow!("ALAS is already starting or restarting"));
+        }
+        Ok(BusyGuard(&self.busy))
+    }
+
+    pub fn is_busy(&self) -> bool {
+        self.busy.load(Ordering::Acquire)
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.backend.lock().unwrap().is_some()
+    }
+
+    /// Checks whether gui.py exited without being stopped, forgetting it if so.
+    pub fn backend_exited(&self) -> Option<ExitStatus> {
+        let status = {
+            let mut backend = self.backend.lock().unwrap();
+            let status = backend.as_mut()?.try_wait().ok()??;
+            backend.take();
+            status
+        };
+        self.state.set(LauncherState::Failed {
+            reason: format!("gui.py exited with {status}"),
+        });
+        Some(status)
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutting_down.load(Ordering::Acquire)
+    }
+
+    pub fn status(&self) -> LauncherStatus {
+        let backend = self.backend.lock().unwrap();
+        LauncherStatus {
+            profile: self.profile.clone(),
+            running: backend.is_some(),
+            busy: self.is_busy(),
+            port: self.target.port(),
+            pid: backend.as_ref().and_then(ManagedBackend::pid),
+            run: self.run.lock().unwrap().clone(),
+            instances: self.instances.get(),
+            state: self.state.get(),
+        }
+    }
+
+    /// Takes the repo lock, which is then held until the launcher exits.
+    fn lock_repo(&self) -> Result<()> {
+        let mut repo_lock = self.repo_lock.lock().unwrap();
+        if repo_lock.is_none() {
+            *repo_lock = Some(RepoLock::acquire(self.target.port())?);
+        }
+        Ok(())
+    }
+
+    pub fn release_repo(&self) {
+        self.repo_lock.lock().unwrap().take();
+    }
+
+    pub fn start(&self, mut status_updater: impl FnMut(&str)) -> Result<()> {
+        let _guard = self.begin()?;
+        self.state.set(LauncherState::Initializing);
+        let result = (|| {
+            if let Target::Remote(remote) = &self.target {
+                return self.connect_remote(remote, status_updater);
+            }
+            self.lock_repo()?;
+            status_updater("Initialize ALAS");
+            setup_alas_repo(&self.state, self.update_on_start, &mut status_updater)?;
+            self.spawn_backend(&[], status_updater)
+        })();
+        self.finish(result)
+    }
+
+    /// Stops processes left by a crashed launcher, returning "pid (name)" of each one killed.
+    pub fn stop_leftovers(
+        &self,
+        leftovers: &[Leftover],
+        status_updater: impl FnMut(&str),
+    ) -> Vec<String> {
+        backend::stop_leftovers(leftovers, &self.shutdown_policy, status_updater)
+    }
+
+    /// Uses a gui.py left by a crashed launcher as the backend, instead of starting a new one.
+    pub fn adopt(&self, leftovers: &[Leftover]) -> Result<()> {
+        let _guard = self.begin()?;
+        self.lock_repo()?;
+        info!("Adopting {} leftover processes", leftovers.len());
+        let b = ManagedBackend::adopt(self.local()?, leftovers)?;
+        self.set_backend(b);
+        Ok(())
+    }
+
+    /// Restarts gui.py, starting the ALAS instances it was running again.
+    ///
+    /// Config files are cleaned up first, as on start, ALAS is only updated when `update` is true.
+    pub fn restart(&self, update: bool, mut status_updater: impl FnMut(&str)) -> Result<()> {
+        let _guard = self.begin()?;
+        let result = (|| {
+            // Restarting a remote ALAS only reconnects to it
+            if let Target::Remote(remote) = &self.target {
+                if update {
+                    return Err(anyhow!(
+                        "ALAS at {} is updated on its own machine",
+                        remote.url
+                    ));
+                }
+                return self.connect_remote(remote, status_updater);
+            }
+            let instances = self.instances.get();
+            self.stop(&mut status_updater)?;
+            setup_alas_repo(&self.state, update, &mut status_updater)?;
+            self.spawn_backend(&instances, status_updater)
+        })();
+        self.finish(result)
+    }
+
+    /// Stops gui.py and leaves it stopped until the next restart.
+    pub fn stop_backend(&self, status_updater: impl FnMut(&str)) -> Result<()> {
+        let _guard = self.begin()?;
+        self.local()?;
+        self.stop(status_updater)?;
+        self.state.set(LauncherState::Stopped);
+        Ok(())
+    }
+
+    /// Stops gui.py before the launcher exits, `is_shutting_down` is true while this runs.
+    ///
+    /// A start or restart in progress is waited for first, it would leave its gui.py behind.
+    pub fn shutdown(&self, mut status_updater: impl FnMut(&str)) -> Result<()> {
+        self.shutting_down.store(true, Ordering::Release);
+        let mut waiting = false;
+        let _guard = loop {
+            if let Ok(guard) = self.begin() {
+                break guard;
+            }
+            if !waiting {
+                waiting = true;
+                info!("Waiting for a start or restart to finish before shutting down");
+                status_updater("Waiting for ALAS to finish starting");
+            }
+            thread::sleep(Duration::from_millis(100));
+        };
+        self.state.set(LauncherState::ShuttingDown);
+        let result = self.stop(status_updater);
+        self.shutting_down.store(false, Ordering::Release);
+        result
+    }
+
+    fn stop(&self, mut status_updater: impl FnMut(&str)) -> Result<()> {
+        let backend = self.backend.lock().unwrap().take();
+        if let Some(mut b) = backend {
+            info!("Stopping gui.py...");
+            if !self.is_shutting_down() {
+                self.state.set(LauncherState::Stopping);
+            }
+            status_updater("Stopping ALAS");
+            let status = b.terminate(&self.shutdown_policy, status_updater)?;
+            info!("gui.py exited with {status}");
+        }
+        self.instances.clear();
+        Ok(())
+    }
+
+    fn connect_remote(
+        &self,
+        remote: &RemoteTarget,
+        mut status_updater: impl FnMut(&str),
+    ) -> Result<()> {
+        info!("Connecting to ALAS on {}", remote.url);
+        self.state.set(LauncherState::StartingBackend);
+        status_updater(&format!("Connecting to {}", remote.url));
+        wait_for_port(&remote.connect_address(), Duration::from_secs(10))?;
+        self.state.set(LauncherState::Running {
+            port: remote.port(),
+            pid: None,
+        });
+        Ok(())
+    }
+
+    /// Starts gui.py, running `instances` on top of the configs to run.
+    fn spawn_backend(
+        &self,
+        instances: &[String],
+        mut status_updater: impl FnMut(&str),
+    ) -> Result<()> {
+        let options = self.local()?;
+        info!("Starting gui.py on {}", options.url());
+        self.state.set(LauncherState::StartingBackend);
+        status_updater("Starting GUI");
+        let mut run = self.run.lock().unwrap().clone();
+        for config in instances {
+            if !run.contains(config) {
+                run.push(config.clone());
+            }
+        }
+        let b = ManagedBackend::new(options, &run, self.log.clone(), self.startup_timeout)?;
+        self.set_backend(b);
+        Ok(())
+    }
+
+    fn set_backend(&self, b: ManagedBackend) {
+        let pid = b.pid();
+        if self.backend.lock().unwrap().replace(b).is_some() {
+            warn!("Replaced a backend that was still running");
+        }
+        self.state.set(LauncherState::Running {
+            port: self.target.port(),
+            pid,
+        });
+    }
+}
@@ -0,0 +1,286 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::{host::Host, notify::NotificationKind, settings::Settings};
+
+/// The same alert is raised at most once in this period, ALAS tends to repeat itself
+const REPEAT_INTERVAL: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    /// Only shown on the tray icon, unless the rule has a notification kind
+    Info,
+    /// Notified as `other` by default
+    Warning,
+    /// Notified as `error` by default
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Info => write!(f, "Info"),
+            Self::Warning => write!(f, "Warning"),
+            Self::Error => write!(f, "Error"),
+        }
+    }
+}
+
+/// A `log_rules` entry in `launcher.yaml`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LogRule {
+    /// Regex matched against each output line of gui.py
+    pub pattern: String,
+    pub severity: Severity,
+    /// `$1` or `${name}` is replaced with what the group of the pattern matched
+    pub message: String,
+    /// Notification to send, instead of the one for the severity
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<NotificationKind>,
+}
+
+impl LogRule {
+    fn new(pattern: &str, severity: Severity, message: &str) -> Self {
+        Self {
+            pattern: pattern.to_owned(),
+            severity,
+            message: message.to_owned(),
+            kind: None,
+        }
+    }
+
+    fn notify_as(mut self, kind: NotificationKind) -> Self {
+        self.kind = Some(kind);
+        self
+    }
+
+    /// Kind of notification an alert from this rule sends, if any.
+    fn notification(&self) -> Option<NotificationKind> {
+        self.kind.or(match self.severity {
+            Severity::Info => None,
+            Severity::Warning => Some(NotificationKind::Other),
+            Severity::Error => Some(NotificationKind::Error),
+        })
+    }
+}
+
+/// Known ALAS failures, used unless `launcher.yaml` has its own `log_rules`.
+pub fn default_rules() -> Vec<LogRule> {
+    vec![
+        LogRule::new(
+            r"(?i)request\s*human\s*takeover",
+            Severity::Error,
+            "ALAS needs you to take over",
+        ),
+        LogRule::new(
+            r"(?i)EmulatorNotRunningError|emulator\b.*\b(disconnected|not running)|device (offline|not found)|adb\b.*\b(closed|offline)",
+            Severity::Error,
+            "Emulator disconnected",
+        ),
+        LogRule::new(
+            r"GameStuckError|GameTooManyClickError|(?i)wait too long|too many click",
+            Severity::Warning,
+            "Game stuck",
+        ),
+        LogRule::new(
+            r"(?i)ocr[\s_-]?server\b.*\b(fail|error|not running|unavailable|timeout|refused)",
+            Severity::Warning,
+            "OCR server failure",
+        ),
+        LogRule::new(
+            r"GameNotRunningError|(?i)game (died|not running)",
+            Severity::Warning,
+            "Game is not running",
+        ),
+        LogRule::new(
+            r"Scheduler: End task `?(\w+)",
+            Severity::Info,
+            "Finished $1",
+        )
+        .notify_as(NotificationKind::TaskFinished),
+    ]
+}
+
+/// A log line matching a rule.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alert {
+    pub severity: Severity,
+    pub message: String,
+    pub line: String,
+    pub kind: Option<NotificationKind>,
+}
+
+/// Compiled `LogRule`s.
+pub struct RuleSet {
+    rules: Vec<(Regex, LogRule)>,
+}
+
+impl RuleSet {
+    pub fn new(rules: &[LogRule]) -> Result<Self> {
+        let rules = rules
+            .iter()
+            .map(|rule| {
+                let regex = Regex::new(&rule.pattern)
+                    .map_err(|e| anyhow!("Invalid log rule {:?}: {e}", rule.pattern))?;
+                Ok((regex, rule.clone()))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { rules })
+    }
+
+    /// Alert for the first rule matching `line`.
+    pub fn check(&self, line: &str) -> Option<Alert> {
+        let (captures, rule) = self
+            .rules
+            .iter()
+            .find_map(|(regex, rule)| Some((regex.captures(line)?, rule)))?;
+        let mut message = String::new();
+        captures.expand(&rule.message, &mut message);
+        Some(Alert {
+            severity: rule.severity,
+            message,
+            line: line.trim().to_owned(),
+            kind: rule.notification(),
+        })
+    }
+}
+
+/// Applies the configured rules to the output of gui.py, raising alerts on the tray icon and as
+/// notifications, or only in the log when headless.
+pub fn watch(host: impl Host, settings: &Settings) {
+    let rules = match RuleSet::new(&settings.log_rules()) {
+        Ok(rules) => rules,
+        Err(e) => {
+            warn!("{e}, not watching ALAS output");
+            return;
+        }
+    };
+    let last_raised = Mutex::new(HashMap::new());
+    let watched = host.clone();
+    watched.launcher().log().subscribe(move |line| {
+        let Some(alert) = rules.check(line) else {
+            return;
+        };
+        {
+            let mut last_raised = last_raised.lock().unwrap();
+            let now = Instant::now();
+            if let Some(last) = last_raised.get(&alert.message) {
+                if now.duration_since(*last) < REPEAT_INTERVAL {
+                    return;
+                }
+            }
+            last_raised.insert(alert.message.clone(), now);
+        }
+        raise(&host, &alert);
+    });
+}
+
+fn raise(host: &impl Host, alert: &Alert) {
+    warn!("{}: {} ({})", alert.severity, alert.message, alert.line);
+    host.set_alert(&alert.message);
+    if let Some(kind) = alert.kind {
+        host.notify(kind, &alert.message, &alert.line);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(line: &str) -> Option<(Severity, String)> {
+        let rules = RuleSet::new(&default_rules()).unwrap();
+        rules
+            .check(line)
+            .map(|alert| (alert.severity, alert.message))
+    }
+
+    #[test]
+    fn test_default_rules() {
+        let error = |message: &str| Some((Severity::Error, message.to_owned()));
+        let warning = |message: &str| Some((Severity::Warning, message.to_owned()));
+        assert_eq!(
+            error("ALAS needs you to take over"),
+            check("2024-05-01 03:50:12.345 | CRITICAL | Request human takeover")
+        );
+        assert_eq!(
+            error("ALAS needs you to take over"),
+            check("module.exception.RequestHumanTakeover")
+        );
+        assert_eq!(
+            error("Emulator disconnected"),
+            check(
+                "2024-05-01 03:50:12.345 | ERROR | EmulatorNotRunningError: Emulator not running"
+            )
+        );
+        assert_eq!(
+            error("Emulator disconnected"),
+            check("2024-05-01 03:50:12.345 | WARNING | adb: device offline")
+        );
+        assert_eq!(
+            warning("Game stuck"),
+            check("2024-05-01 03:50:12.345 | ERROR | GameStuckError: Wait too long")
+        );
+        assert_eq!(
+            warning("Game stuck"),
+            check("2024-05-01 03:50:12.345 | WARNING | Too many click for a button: GOTO_MAIN")
+        );
+        assert_eq!(
+            warning("OCR server failure"),
+            check("2024-05-01 03:50:12.345 | ERROR | Ocr server not running, connection refused")
+        );
+        assert_eq!(
+            warning("Game is not running"),
+            check("2024-05-01 03:50:12.345 | ERROR | GameNotRunningError: Game died")
+        );
+        assert_eq!(
+            None,
+            check("2024-05-01 03:50:12.345 | INFO | Emulator connected: 127.0.0.1:16384")
+        );
+        assert_eq!(
+            None,
+            check("2024-05-01 03:50:12.345 | INFO | [Task] Commission (Enabled)")
+        );
+        assert_eq!(
+            Some((Severity::Info, "Finished Commission".to_owned())),
+            check("2024-05-01 03:50:12.345 | INFO | Scheduler: End task `Commission`")
+        );
+        let rules = RuleSet::new(&default_rules()).unwrap();
+        let kind = |line: &str| rules.check(line).unwrap().kind;
+        assert_eq!(
+            Some(NotificationKind::TaskFinished),
+            kind("Scheduler: End task `Commission`")
+        );
+        assert_eq!(
+            Some(NotificationKind::Error),
+            kind("Request human takeover")
+        );
+        assert_eq!(Some(NotificationKind::Other), kind("GameStuckError"));
+    }
+
+    #[test]
+    fn test_custom_rules() {
+        let rules = RuleSet::new(&[LogRule::new(
+            r"Scheduler stopped",
+            Severity::Info,
+            "Scheduler stopped",
+        )])
+        .unwrap();
+        let alert = rules.check("  INFO | Scheduler stopped  ").unwrap();
+        assert_eq!(Severity::Info, alert.severity);
+        assert_eq!(None, alert.kind);
+        assert_eq!("INFO | Scheduler stopped", alert.line);
+        assert_eq!(None, rules.check("Request human takeover"));
+        assert!(RuleSet::new(&[LogRule::new("(", Severity::Error, "")]).is_err());
+    }
+}
@@ -1,28 +1,57 @@
 // No default console window createion on Windows
 #![windows_subsystem = "windows"]
 
+mod autostart;
 mod backend;
+mod cli;
+mod control;
+mod download;
+mod headless;
+mod host;
+mod launcher;
+mod log_watch;
+mod navigation;
+mod notify;
+mod picker;
+mod process_tree;
+mod profile;
+mod repo_lock;
+mod scheduler;
+mod settings;
+mod service;
+mod settings_window;
 mod setup;
+mod state;
+mod tray;
+mod upload;
+mod watchdog;
+mod webui;
+mod window_state;
 mod window_util;
 
-use std::{
-    fs,
-    sync::{Arc, Mutex},
-    thread::{self},
-};
+use std::{fs, sync::Mutex, thread};
 
 use anyhow::{anyhow, Result};
 use base64::{prelude::BASE64_STANDARD, Engine};
 use tauri::{
     webview::{PageLoadEvent, PageLoadPayload},
-    Manager, Url, WebviewWindow,
+    AppHandle, Emitter, Manager, Url, WebviewWindow,
 };
-use tauri_plugin_dialog::{DialogExt, FilePath};
-use tracing::{error, info, warn};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+use tracing::{error, info, warn, Level};
 
 use crate::{
-    backend::ManagedBackend,
-    setup::{get_deploy_config, setup_alas_repo, setup_environment},
+    cli::{parse_action, parse_args, Action, Invocation, USAGE},
+    download::DownloadResult,
+    launcher::Launcher,
+    notify::NotificationKind,
+    process_tree::find_leftovers,
+    profile::{Choice, Profiles},
+    settings::Settings,
+    settings_window::SettingsState,
+    setup::{get_deploy_config, setup_environment},
+    state::LauncherState,
+    webui::{RemoteTarget, Target, WebuiOptions},
 };
 
 fn main() -> Result<()> {
@@ -33,33 +62,220 @@ fn main() -> Result<()> {
         use winapi::um::wincon::{AttachConsole, ATTACH_PARENT_PROCESS};
         HAS_CONSOLE.store(AttachConsole(ATTACH_PARENT_PROCESS) != 0, Ordering::Relaxed);
     }
-    tracing_subscriber::fmt::init();
-    setup_environment()?;
+    let settings = Settings::load();
+    let log_level = settings.as_ref().map_or(Level::INFO, Settings::log_level);
+    // journald adds its own timestamps and doesn't render colors
+    let journald = std::env::var_os("JOURNAL_STREAM").is_some();
+    let subscriber = tracing_subscriber::fmt().with_max_level(log_level).with_ansi(!journald);
+    if journald {
+        subscriber.without_time().init();
+    } else {
+        subscriber.init();
+    }
+    // Shown once the launcher window is up, Windows users never see the log
+    let mut warnings = Vec::new();
+    let settings = settings.unwrap_or_else(|e| {
+        let warning = format!("{e}, using default settings");
+        warn!("{warning}");
+        warnings.push(warning);
+        Settings::default()
+    });
 
-    let port = get_deploy_config()
-        .as_ref()
-        .and_then(|config| config.get("Deploy"))
-        .and_then(|deploy| deploy.get("Webui"))
-        .and_then(|webui| webui.get("WebuiPort"))
-        .and_then(|port| port.as_u64());
-    if port.is_none() {
-        warn!("WebuiPort not found in config, using default port 22267");
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let invocation = match parse_args(&args) {
+        Ok(invocation) => invocation,
+        Err(e) => {
+            eprintln!("{e}\n\n{USAGE}");
+            std::process::exit(2);
+        }
+    };
+    let context = tauri::generate_context!();
+    let profiles = Profiles::load().unwrap_or_else(|e| {
+        let warning = format!("{e}, starting without profiles");
+        warn!("{warning}");
+        warnings.push(warning);
+        Profiles::default()
+    });
+    let (action, profile, remote, minimized, headless) = match invocation {
+        Invocation::Launch {
+            action,
+            profile,
+            remote,
+            minimized,
+            headless,
+        } => match profiles.choose(profile.as_deref())? {
+            Choice::Default => (action, None, remote, minimized, headless),
+            Choice::Profile(profile) => (action, Some(profile.clone()), remote, minimized, headless),
+            Choice::Ask if headless => {
+                eprintln!("Several profiles are configured, pass --profile NAME");
+                std::process::exit(2);
+            }
+            Choice::Ask => return picker::run(profiles.profiles, args, context),
+        },
+        Invocation::Ctl {
+            action,
+            profile,
+            json,
+        } => match profiles.choose(profile.as_deref())? {
+            Choice::Default => run_ctl(None, &action, json),
+            Choice::Profile(profile) => run_ctl(Some(&profile.name), &action, json),
+            Choice::Ask => {
+                eprintln!("Several profiles are configured, pass --profile NAME");
+                std::process::exit(2);
+            }
+        },
+        Invocation::Autostart { command, profile } => {
+            // Without a profile the picker is shown at login
+            let profile = match profiles.choose(profile.as_deref())? {
+                Choice::Profile(profile) => Some(profile.name.as_str()),
+                Choice::Default | Choice::Ask => None,
+            };
+            autostart::run_cli(profile, command)
+        }
+        Invocation::Service { command, profile } => match profiles.choose(profile.as_deref())? {
+            Choice::Default => service::run_cli(None, command, &settings),
+            Choice::Profile(profile) => service::run_cli(Some(profile), command, &settings),
+            Choice::Ask => {
+                eprintln!("Several profiles are configured, pass --profile NAME");
+                std::process::exit(2);
+            }
+        },
+    };
+    let profile_name = profile.as_ref().map(|p| p.name.as_str());
+    // A service must not take over from, or hand its action to, a running launcher
+    if headless && control::forward(profile_name, &Action::Status).is_some() {
+        return Err(anyhow!("alas-launcher is already running"));
+    }
+    // Starting minimized, e.g. at login, leaves a running launcher alone
+    let forwarded = if minimized && action == Action::Show {
+        &Action::Status
+    } else {
+        &action
+    };
+    match control::forward(profile_name, forwarded) {
+        Some(Ok(reply)) if reply.ok => {
+            println!("{}", reply.message);
+            return Ok(());
+        }
+        Some(Ok(reply)) => {
+            eprintln!("{}", reply.message);
+            std::process::exit(1);
+        }
+        Some(Err(e)) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+        None if matches!(action, Action::Logs { .. }) => {
+            eprintln!("alas-launcher is not running");
+            std::process::exit(1);
+        }
+        None => {}
     }
-    let port = port.unwrap_or(22267) as u16;
 
-    let backend = Arc::new(Mutex::new(None));
+    let remote = remote.map(|url| (url, None)).or_else(|| {
+        let profile = profile.as_ref()?;
+        Some((profile.remote.clone()?, profile.password.clone()))
+    });
+    let target = match remote {
+        Some((url, password)) => {
+            let password = password.or_else(|| std::env::var("ALAS_REMOTE_PASSWORD").ok());
+            Target::Remote(RemoteTarget::new(&url, password)?)
+        }
+        None => {
+            setup_environment(profile.as_ref().and_then(|p| p.repo.as_deref()))?;
+            let mut options = WebuiOptions::from_deploy_config(get_deploy_config().as_ref());
+            if let Some(port) = profile.as_ref().and_then(|p| p.port) {
+                options.port = port;
+            }
+            if let Some(warning) = options.ignored_host_warning() {
+                warn!("{warning}");
+                warnings.push(warning);
+            }
+            Target::Local(options)
+        }
+    };
+    let launcher = Launcher::new(target, profile.as_ref(), &settings);
+    if let Action::Run { configs } = &action {
+        launcher.add_run(configs);
+    }
+    if headless {
+        if launcher.is_remote() {
+            return Err(anyhow!("--headless can't be used with a remote ALAS"));
+        }
+        return headless::run(launcher, &settings);
+    }
 
     info!("Starting Webview...");
-    tauri::Builder::default()
-        .invoke_handler(tauri::generate_handler![save_as])
+    let window_size = (settings.window.width, settings.window.height);
+    let tray_enabled = settings.tray.enabled;
+    let watched_settings = settings.clone();
+    let mut builder = tauri::Builder::default()
+        .manage(launcher)
+        .manage(SettingsState(Mutex::new(settings)))
+        .invoke_handler(tauri::generate_handler![
+            save_as,
+            upload::open_file,
+            navigation::open_external,
+            notify::notify,
+            launcher_state,
+            restart_backend,
+            update_and_restart,
+            settings_window::get_settings,
+            settings_window::save_settings,
+            settings_window::open_settings,
+            autostart::autostart_status,
+            autostart::set_autostart
+        ])
         .plugin(tauri_plugin_dialog::init())
-        .plugin(tauri_plugin_single_instance::init(|app, _argv, _cwd| {
-            let _ = app
-                .get_webview_window("main")
-                .and_then(|w| w.set_focus().ok());
-        }))
-        .setup(|app| {
-            tauri::WebviewWindowBuilder::from_config(
+        // Links are handled by page_load_injector, the plugin's own handler can't call it from the WebUI
+        .plugin(tauri_plugin_opener::Builder::new().open_js_links_on_click(false).build());
+    // Launchers of different profiles run side by side, each only reachable through its own channel
+    if profile.is_none() {
+        builder = builder.plugin(tauri_plugin_single_instance::init(|app, argv, _cwd| {
+            // Only reached if the second instance couldn't use the instance channel
+            let app = app.clone();
+            thread::spawn(move || {
+                let args = argv.get(1..).unwrap_or_default();
+                match parse_action(args).and_then(|action| control::execute(&app, action)) {
+                    Ok(reply) => info!("{}", reply.message),
+                    Err(e) => warn!("Failed to run forwarded action: {e}"),
+                }
+            });
+        }));
+    }
+    builder
+        .setup(move |app| {
+            if tray_enabled {
+                tray::create(app)?;
+            }
+            if !warnings.is_empty() {
+                app.dialog()
+                    .message(warnings.join("\n\n"))
+                    .title("ALAS launcher")
+                    .kind(MessageDialogKind::Warning)
+                    .show(|_| {});
+            }
+            let handle = app.handle().clone();
+            app.state::<Launcher>().state().subscribe(move |state| {
+                if let Err(e) = handle.emit("launcher-state", state) {
+                    warn!("Failed to emit launcher state: {e}");
+                }
+            });
+            log_watch::watch(app.handle().clone(), &watched_settings);
+            scheduler::start(app.handle().clone(), &watched_settings.schedule);
+            watchdog::start(app.handle().clone(), &watched_settings.watchdog);
+            let handle = app.handle().clone();
+            app.state::<Launcher>().state().subscribe(move |state| {
+                if let LauncherState::Failed { reason } = state {
+                    notify::send(&handle, NotificationKind::Launcher, "ALAS failed", reason);
+                }
+            });
+            let launcher = app.state::<Launcher>();
+            if let Err(e) = control::listen(app.handle().clone(), launcher.profile()) {
+                warn!("Failed to listen for forwarded actions: {e}");
+            }
+            let handle = app.handle().clone();
+            let mut builder = tauri::WebviewWindowBuilder::from_config(
                 app,
                 app.config()
                     .app
@@ -69,10 +285,20 @@ fn main() -> Result<()> {
                     .unwrap(),
             )?
             .on_page_load(page_load_injector)
-            .build()?;
+            .on_download(download::handle)
+            .on_navigation(move |url| navigation::on_navigation(&handle, url))
+            .inner_size(window_size.0, window_size.1);
+            if let Some(profile) = launcher.profile() {
+                builder = builder.title(format!("Alas - {profile}"));
+            }
+            // Runs before pywebio's own scripts, unlike the page load injection
+            if let Some(script) = launcher.target().login_script() {
+                builder = builder.initialization_script(&script);
+            }
+            builder.build()?;
             Ok(())
         })
-        .build(tauri::generate_context!())?
+        .build(context)?
         .run(move |app_handle, event| {
             match event {
                 tauri::RunEvent::Ready => {
@@ -82,45 +308,83 @@ fn main() -> Result<()> {
                         handle1.exit(0);
                     }).expect("Error setting Ctrl-C handler");
                     let app_handle = app_handle.clone();
-                    let backend = backend.clone();
                     thread::spawn(move || {
                         let splash = app_handle.get_webview_window("splash").unwrap();
-                        let status_updater = |text: &str| {
-                            let content = format!("Loading ALAS, please wait..\n\n{}", text);
-                            let url = Url::parse(&text_to_splash(&content)).unwrap();
-                            splash.navigate(url).unwrap();
-                        };
-                        status_updater("Initialize ALAS");
-                        if let Err(e) = setup_alas_repo(&status_updater) {
-                            error!("{e}");
-                            let content = format!("Failed loading ALAS, reason: {}\n\nPlease run alas-launcher from terminal for detailed logs", e);
-                            let url = Url::parse(&text_to_splash(&content)).unwrap();
-                            splash.navigate(url).unwrap();
-                            return;
+                        let in_tray = app_handle.tray_by_id(tray::TRAY_ID).is_some();
+                        if minimized && in_tray {
+                            let _ = splash.hide();
+                        } else if minimized {
+                            let _ = splash.minimize();
+                        }
+                        let status_updater = |text: &str| show_status(&splash, text);
+                        let launcher = app_handle.state::<Launcher>();
+                        if launcher.is_remote() || !handle_leftovers(&app_handle, &launcher, status_updater) {
+                            if let Err(e) = launcher.start(status_updater) {
+                                error!("{e}");
+                                show_failure(&splash, &e);
+                                return;
+                            }
                         }
-                        info!("Starting gui.py on http://127.0.0.1:{}/", port);
-                        status_updater("Starting GUI");
-                        let b = ManagedBackend::new(port).unwrap();
-                        *backend.lock().unwrap() = Some(b);
                         splash.destroy().unwrap();
                         info!("Webview is ready");
                         let window = app_handle.get_webview_window("main").unwrap();
-                        window
-                            .navigate(Url::parse(&format!("http://127.0.0.1:{}/", port)).unwrap())
-                            .unwrap();
+                        window_state::restore(&window);
+                        navigation::navigate(&window, launcher.target().url());
+                        if minimized && in_tray {
+                            return;
+                        }
                         window.show().unwrap();
+                        if minimized {
+                            let _ = window.minimize();
+                        }
                     });
                 }
-                tauri::RunEvent::ExitRequested { .. } => {
-                    info!("Webview closed, shutting down backend...");
-                    if let Some(ref mut b) = *backend.lock().unwrap() {
-                        if let Err(e) = b.terminate() {
-                            warn!("Failed to terminate backend process: {:?}", e);
-                        }
+                tauri::RunEvent::ExitRequested { api, .. } => {
+                    if let Some(window) = app_handle.get_webview_window("main") {
+                        window_state::save(&window);
+                    }
+                    let launcher = app_handle.state::<Launcher>();
+                    if launcher.is_shutting_down() {
+                        api.prevent_exit();
+                    } else if launcher.is_running() || launcher.is_busy() {
+                        info!("Webview closed, shutting down backend...");
+                        api.prevent_exit();
+                        let app_handle = app_handle.clone();
+                        thread::spawn(move || {
+                            let window = app_handle.get_webview_window("main");
+                            let status_updater = |text: &str| {
+                                if let Some(window) = &window {
+                                    show_text(window, &format!("Stopping ALAS, please wait..\n\n{}", text));
+                                }
+                            };
+                            if let Err(e) = app_handle.state::<Launcher>().shutdown(status_updater) {
+                                warn!("Failed to terminate backend process: {:?}", e);
+                            }
+                            app_handle.exit(0);
+                        });
                     }
                 }
-                tauri::RunEvent::WindowEvent { label, event: tauri::WindowEvent::CloseRequested { .. }, .. } => {
+                tauri::RunEvent::Exit => {
+                    app_handle.state::<Launcher>().release_repo();
+                }
+                tauri::RunEvent::WindowEvent { label, event: tauri::WindowEvent::CloseRequested { api, .. }, .. } => {
                     info!("Window {} closed", label);
+                    if label == "settings" {
+                        return;
+                    }
+                    let close_to_tray = app_handle.state::<SettingsState>().0.lock().unwrap().tray.close_to_tray;
+                    if label == "main" && close_to_tray && app_handle.tray_by_id(tray::TRAY_ID).is_some() {
+                        api.prevent_close();
+                        if let Some(window) = app_handle.get_webview_window("main") {
+                            window_state::save(&window);
+                            let _ = window.hide();
+                        }
+                        return;
+                    }
+                    // Keep the main window open to show shutdown progress, it goes away on exit
+                    if label == "main" {
+                        api.prevent_close();
+                    }
                     app_handle.exit(0);
                 }
                 _ => {}
@@ -129,58 +393,323 @@ fn main() -> Result<()> {
     Ok(())
 }
 
+/// Sends a `ctl` command to the launcher running `profile` and exits with its result.
+fn run_ctl(profile: Option<&str>, action: &Action, json: bool) -> ! {
+    let reply = match control::forward(profile, action) {
+        Some(Ok(reply)) => reply,
+        Some(Err(e)) => {
+            eprintln!("Failed to talk to alas-launcher: {e}");
+            std::process::exit(1);
+        }
+        None => {
+            eprintln!("alas-launcher is not running");
+            std::process::exit(1);
+        }
+    };
+    if json {
+        println!("{}", serde_json::to_string_pretty(&reply).unwrap());
+    } else if reply.ok {
+        println!("{}", reply.message);
+    } else {
+        eprintln!("{}", reply.message);
+    }
+    std::process::exit(if reply.ok { 0 } else { 1 });
+}
+
+/// Asks whether processes left by a crashed launcher should be terminated or adopted.
+/// Returns true if they were adopted as the running backend.
+fn handle_leftovers(
+    app_handle: &AppHandle,
+    launcher: &Launcher,
+    status_updater: impl FnMut(&str),
+) -> bool {
+    let leftovers = find_leftovers();
+    if leftovers.is_empty() {
+        return false;
+    }
+    let mut list = leftovers
+        .iter()
+        .take(10)
+        .map(|l| format!("[{}] {} (launcher {})", l.pid, l.name, l.launcher_pid))
+        .collect::<Vec<_>>()
+        .join("\n");
+    if leftovers.len() > 10 {
+        list.push_str(&format!("\n...and {} more", leftovers.len() - 10));
+    }
+    warn!("Found processes left by a previous launcher:\n{list}");
+    let terminate = app_handle
+        .dialog()
+        .message(format!("These processes were started by a previous ALAS launcher that did not exit cleanly:\n\n{list}\n\nTerminate them, or adopt them and keep using the running ALAS?\n\nThe output of adopted processes is lost, logs and alerts stay empty until ALAS is restarted."))
+        .title("ALAS is still running")
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            "Terminate".to_owned(),
+            "Adopt".to_owned(),
+        ))
+        .blocking_show();
+    if terminate {
+        for process in launcher.stop_leftovers(&leftovers, status_updater) {
+            warn!("Killed leftover process {process}");
+        }
+        return false;
+    }
+    match launcher.adopt(&leftovers) {
+        Ok(()) => true,
+        Err(e) => {
+            // The adopted processes were killed when the backend was dropped
+            warn!("Failed to adopt leftover processes, terminated them instead: {e}");
+            false
+        }
+    }
+}
+
 #[tauri::command]
-fn save_as(app_handle: tauri::AppHandle, filename: &str, data: &str) {
-    match BASE64_STANDARD.decode(data) {
-        Ok(decoded_data) => app_handle
-            .dialog()
-            .file()
-            .set_file_name(filename)
-            .save_file(move |path| {
-                let result: Result<()> = (move || {
-                    let file_path = path
-                        .as_ref()
-                        .and_then(FilePath::as_path)
-                        .ok_or_else(|| anyhow!("Invalid file path {:?}", &path))?;
-                    fs::write(file_path, &decoded_data)?;
-                    info!("Saved file to {:?}", file_path);
-                    Ok(())
-                })();
-                if let Err(e) = result {
-                    error!("Failed to save file: {:?}", e);
-                }
-            }),
+fn launcher_state(app_handle: AppHandle) -> LauncherState {
+    app_handle.state::<Launcher>().state().get()
+}
+
+#[tauri::command]
+fn restart_backend(app_handle: AppHandle) -> Result<(), String> {
+    spawn_restart(app_handle, false)
+}
+
+#[tauri::command]
+fn update_and_restart(app_handle: AppHandle) -> Result<(), String> {
+    spawn_restart(app_handle, true)
+}
+
+fn spawn_restart(app_handle: AppHandle, update: bool) -> Result<(), String> {
+    if app_handle.state::<Launcher>().is_busy() {
+        return Err("ALAS is already starting or restarting".to_owned());
+    }
+    thread::spawn(move || {
+        let _ = restart_with_progress(&app_handle, update);
+    });
+    Ok(())
+}
+
+/// Restarts gui.py showing progress in the main window, blocking until it's done.
+fn restart_with_progress(app_handle: &AppHandle, update: bool) -> Result<()> {
+    let launcher = app_handle.state::<Launcher>();
+    // Don't let the error replace the progress of the ongoing restart
+    if launcher.is_busy() {
+        return Err(anyhow!("ALAS is already starting or restarting"));
+    }
+    let window = app_handle.get_webview_window("main").unwrap();
+    let status_updater = |text: &str| show_status(&window, text);
+    match launcher.restart(update, status_updater) {
+        Ok(()) => {
+            info!("gui.py restarted");
+            navigation::navigate(&window, launcher.target().url());
+            Ok(())
+        }
         Err(e) => {
-            error!("Failed to decode file content: {:?}", e);
+            error!("{e}");
+            show_failure(&window, &e);
+            Err(e)
         }
     }
 }
 
+/// Stops gui.py showing progress in the main window, blocking until it's done.
+fn stop_with_progress(app_handle: &AppHandle) -> Result<()> {
+    let launcher = app_handle.state::<Launcher>();
+    if launcher.is_busy() {
+        return Err(anyhow!("ALAS is already starting or restarting"));
+    }
+    let window = app_handle.get_webview_window("main").unwrap();
+    let status_updater = |text: &str| show_text(&window, &format!("Stopping ALAS, please wait..\n\n{}", text));
+    launcher.stop_backend(status_updater)?;
+    info!("gui.py stopped");
+    show_text(&window, "ALAS is stopped\n\nPress Ctrl+Shift+R to start it again");
+    Ok(())
+}
+
+fn show_main_window(app_handle: &AppHandle) {
+    tray::set_alert(app_handle, None);
+    // The splash screen stands in for the main window until ALAS is loaded
+    let window = app_handle
+        .get_webview_window("splash")
+        .or_else(|| app_handle.get_webview_window("main"));
+    if let Some(window) = window {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+fn show_text(window: &WebviewWindow, content: &str) {
+    let url = Url::parse(&text_to_splash(content)).unwrap();
+    navigation::navigate(window, url);
+}
+
+fn show_status(window: &WebviewWindow, text: &str) {
+    show_text(window, &format!("Loading ALAS, please wait..\n\n{}", text));
+}
+
+fn show_failure(window: &WebviewWindow, e: &anyhow::Error) {
+    show_text(window, &format!("Failed loading ALAS, reason: {}\n\nPlease run alas-launcher from terminal for detailed logs", e));
+}
+
+/// Saves a file the WebUI exported with `saveAs`, after asking where to put it.
+#[tauri::command]
+async fn save_as(app_handle: tauri::AppHandle, filename: String, data: String) -> DownloadResult {
+    let task = tauri::async_runtime::spawn_blocking(move || {
+        let decoded_data = match BASE64_STANDARD.decode(&data) {
+            Ok(decoded_data) => decoded_data,
+            Err(e) => {
+                error!("Failed to decode content of {filename}: {e}");
+                return DownloadResult::Failed { filename, error: format!("Invalid file content: {e}") };
+            }
+        };
+        let Some(path) = app_handle.dialog().file().set_file_name(&filename).blocking_save_file() else {
+            return DownloadResult::Cancelled { filename };
+        };
+        let result: Result<_> = (|| {
+            let file_path = path.as_path().ok_or_else(|| anyhow!("Invalid file path {:?}", &path))?;
+            fs::write(file_path, &decoded_data)?;
+            Ok(file_path.to_owned())
+        })();
+        match result {
+            Ok(path) => {
+                info!("Saved {} bytes to {:?}", decoded_data.len(), path);
+                DownloadResult::Saved { filename, path }
+            }
+            Err(e) => {
+                error!("Failed to save file: {:?}", e);
+                DownloadResult::Failed { filename, error: e.to_string() }
+            }
+        }
+    });
+    task.await.unwrap_or_else(|e| DownloadResult::Failed { filename: String::new(), error: e.to_string() })
+}
+
 fn page_load_injector(webview: WebviewWindow, payload: PageLoadPayload<'_>) {
     if payload.event() == PageLoadEvent::Finished {
         info!(
-            "Injecting saveFile function to loaded page: {}",
+            "Injecting launcher functions to loaded page: {}",
             payload.url()
         );
         let injected_js = r#"
 if (!window.alas_launcher_injected) {
     window.alas_launcher_injected = true;
     (function () {
-        // Prevent going back
-        history.pushState(null, document.title, location.href);
-        window.addEventListener('popstate', event => {
-            history.pushState(null, document.title, location.href);
-        });
-        // Overwrite original saveAs function
+        // Overwrite original saveAs function, resolves to the result of save_as
         window.saveAs = function (blob, filename) {
-            const reader = new FileReader();
-            reader.onload = async () => {
-                const data = reader.result.split(',')[1];
-                console.log(data);
-                window.__TAURI__.core.invoke('save_as', { filename, data });
-            };
-            reader.readAsDataURL(blob);
+            return new Promise((resolve, reject) => {
+                const reader = new FileReader();
+                reader.onload = () => resolve(reader.result.split(',')[1]);
+                reader.onerror = () => reject(reader.error);
+                reader.readAsDataURL(blob);
+            }).then(
+                data => window.__TAURI__.core.invoke('save_as', { filename, data })
+            ).catch(
+                e => ({ status: 'failed', filename, error: String(e) })
+            ).then(result => {
+                window.dispatchEvent(new CustomEvent('alas-launcher-download', { detail: result }));
+                return result;
+            });
+        };
+        // File inputs open the native dialog through open_file, see upload.rs
+        document.addEventListener('click', event => {
+            const input = event.target;
+            if (!(input instanceof HTMLInputElement) || input.type !== 'file' || input.disabled) {
+                return;
+            }
+            event.preventDefault();
+            window.__TAURI__.core.invoke('open_file', { accept: input.accept || null, multiple: input.multiple })
+                .catch(e => ({ status: 'failed', error: String(e) }))
+                .then(result => {
+                    if (result.status === 'failed') {
+                        window.alasLauncherToast('Failed to open file: ' + result.error, true);
+                    }
+                    if (result.status !== 'opened') {
+                        return;
+                    }
+                    const transfer = new DataTransfer();
+                    for (const file of result.files) {
+                        const bytes = Uint8Array.from(atob(file.data), c => c.charCodeAt(0));
+                        transfer.items.add(new File([bytes], file.name, { type: file.mime }));
+                    }
+                    input.files = transfer.files;
+                    input.dispatchEvent(new Event('input', { bubbles: true }));
+                    input.dispatchEvent(new Event('change', { bubbles: true }));
+                });
+        }, true);
+        // Links to other sites open in the browser, so do new windows, see navigation.rs
+        const openWindow = url => {
+            const target = new URL(url, location.href);
+            if (target.origin === location.origin) {
+                location.href = target.href;
+            } else {
+                window.__TAURI__.core.invoke('open_external', { url: target.href })
+                    .catch(e => console.warn(e));
+            }
         };
+        window.open = url => {
+            if (url) {
+                openWindow(String(url));
+            }
+            return null;
+        };
+        document.addEventListener('click', event => {
+            const link = event.target instanceof Element && event.target.closest('a[href]');
+            if (link && link.target === '_blank' && !link.hasAttribute('download')) {
+                event.preventDefault();
+                openWindow(link.href);
+            }
+        }, true);
+        // Desktop notifications, kind is one of task_finished, error, launcher or other
+        window.alasLauncherNotify = function (kind, title, body) {
+            return window.__TAURI__.core.invoke('notify', { kind, title: String(title), body: body == null ? null : String(body) });
+        };
+        window.Notification = class {
+            static permission = 'granted';
+            static requestPermission() {
+                return Promise.resolve('granted');
+            }
+            constructor(title, options) {
+                window.alasLauncherNotify('other', title, options && options.body).catch(e => console.warn(e));
+            }
+            close() {}
+        };
+        // Small notice in the corner of the page
+        window.alasLauncherToast = function (text, error) {
+            const toast = document.createElement('div');
+            toast.textContent = text;
+            toast.style.cssText = 'position:fixed;right:16px;bottom:16px;z-index:2147483647;max-width:480px;'
+                + 'padding:8px 12px;border-radius:6px;color:#fff;font:13px system-ui,sans-serif;'
+                + 'box-shadow:0 2px 8px rgba(0,0,0,.3);background:' + (error ? '#cf222e' : '#1f2328');
+            document.body.appendChild(toast);
+            setTimeout(() => toast.remove(), 5000);
+        };
+        // Downloads and saveAs exports are saved by the launcher, see download.rs
+        window.addEventListener('alas-launcher-download', event => {
+            const result = event.detail;
+            if (result.status === 'saved') {
+                window.alasLauncherToast('Saved ' + result.path);
+            } else if (result.status === 'cancelled') {
+                window.alasLauncherToast('Did not save ' + result.filename);
+            } else if (result.status === 'failed') {
+                window.alasLauncherToast('Failed to save ' + result.filename + ': ' + result.error, true);
+            }
+        });
+        // Ctrl/Cmd+Shift+R restarts gui.py, Ctrl/Cmd+Shift+U updates ALAS and restarts it,
+        // Ctrl/Cmd+Shift+S opens the launcher settings
+        document.addEventListener('keydown', event => {
+            if (!(event.ctrlKey || event.metaKey) || !event.shiftKey) {
+                return;
+            }
+            const command = {
+                KeyR: 'restart_backend',
+                KeyU: 'update_and_restart',
+                KeyS: 'open_settings',
+            }[event.code];
+            if (command) {
+                event.preventDefault();
+                window.__TAURI__.core.invoke(command).catch(e => console.warn(e));
+            }
+        });
     })();
 }
 "#;
@@ -190,17 +719,23 @@ if (!window.alas_launcher_injected) {
     }
 }
 
-fn text_to_splash(s: &str) -> String {
+fn escape_html(s: &str) -> String {
     let mut out = String::with_capacity(s.len());
     for ch in s.chars() {
         match ch {
             '&' => out.push_str("&amp;"),
             '<' => out.push_str("&lt;"),
             '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
             '\r' => {} // drop CR, keep LF
             other => out.push(other),
         }
     }
+    out
+}
+
+fn text_to_splash(s: &str) -> String {
+    let out = escape_html(s);
     let html = format!(
         r#"<!doctype html>
 <html>
@@ -220,6 +755,10 @@ fn text_to_splash(s: &str) -> String {
         out
     );
 
+    html_to_url(&html)
+}
+
+fn html_to_url(html: &str) -> String {
     let b64 = BASE64_STANDARD.encode(html.as_bytes());
     format!("data:text/html;charset=utf-8;base64,{}", b64)
 }
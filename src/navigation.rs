@@ -0,0 +1,152 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+};
+
+use tauri::{AppHandle, Manager, Url, WebviewWindow};
+use tauri_plugin_opener::OpenerExt;
+use tracing::{info, warn};
+
+use crate::launcher::Launcher;
+
+/// Status pages the launcher navigated the main window to, not yet seen by the navigation hook,
+/// oldest first. Progress updates come faster than the webview navigates.
+static EXPECTED: Mutex<VecDeque<Url>> = Mutex::new(VecDeque::new());
+/// More pending status pages than this were superseded without ever being navigated to
+const MAX_EXPECTED: usize = 64;
+/// Whether the main window has shown the WebUI yet
+static WEBUI_SHOWN: AtomicBool = AtomicBool::new(false);
+
+/// Shows a launcher page in `window`, which the navigation guard lets through once.
+pub fn navigate(window: &WebviewWindow, url: Url) {
+    if window.label() == "main" && is_launcher_page(&url) {
+        expect(&mut EXPECTED.lock().unwrap(), url.clone());
+    }
+    window.navigate(url).unwrap();
+}
+
+/// Navigation hook of the main window: the WebUI stays in the window, everything else goes to
+/// the system browser.
+///
+/// Going back to the blank page or a status page the window started with is blocked, in-app
+/// history of the WebUI is left alone.
+pub fn on_navigation(app_handle: &AppHandle, url: &Url) -> bool {
+    if is_launcher_page(url) {
+        let expected = take_expected(&mut EXPECTED.lock().unwrap(), url);
+        if !expected && WEBUI_SHOWN.load(Ordering::Relaxed) {
+            info!("Blocked navigation back to a launcher page");
+            return false;
+        }
+        return true;
+    }
+    let webui = app_handle.state::<Launcher>().target().url();
+    if is_internal(url, &webui) {
+        if url.origin() == webui.origin() {
+            WEBUI_SHOWN.store(true, Ordering::Relaxed);
+        }
+        return true;
+    }
+    open_in_browser(app_handle, url);
+    false
+}
+
+fn expect(expected: &mut VecDeque<Url>, url: Url) {
+    if expected.len() == MAX_EXPECTED {
+        expected.pop_front();
+    }
+    expected.push_back(url);
+}
+
+// Pages navigated to before `url` were superseded by it, so they're dropped too
+fn take_expected(expected: &mut VecDeque<Url>, url: &Url) -> bool {
+    let Some(index) = expected.iter().position(|e| e == url) else {
+        return false;
+    };
+    expected.drain(..=index);
+    true
+}
+
+/// The blank page windows start with, and the status pages of `show_text`.
+fn is_launcher_page(url: &Url) -> bool {
+    url.as_str() == "about:blank" || url.scheme() == "data"
+}
+
+/// Pages of the WebUI, and the launcher's own status pages.
+fn is_internal(url: &Url, webui: &Url) -> bool {
+    // Tauri's own pages and IPC on Windows
+    let is_tauri = matches!(url.scheme(), "http" | "https")
+        && url.port().is_none()
+        && url
+            .host_str()
+            .is_some_and(|host| host.ends_with(".localhost") && host.split('.').count() == 2);
+    matches!(url.scheme(), "about" | "data" | "tauri" | "ipc")
+        || url.origin() == webui.origin()
+        || is_tauri
+}
+
+fn open_in_browser(app_handle: &AppHandle, url: &Url) {
+    if !matches!(url.scheme(), "http" | "https" | "mailto") {
+        warn!("Blocked navigation to {url}");
+        return;
+    }
+    info!("Opening {url} in the browser");
+    if let Err(e) = app_handle.opener().open_url(url.as_str(), None::<&str>) {
+        warn!("Failed to open {url}: {e}");
+    }
+}
+
+/// Opens a link the WebUI wanted in a new window, see the `target=_blank` and `window.open`
+/// hooks in `page_load_injector`.
+#[tauri::command]
+pub fn open_external(app_handle: AppHandle, url: &str) -> Result<(), String> {
+    let url = Url::parse(url).map_err(|e| e.to_string())?;
+    let webui = app_handle.state::<Launcher>().target().url();
+    if is_internal(&url, &webui) {
+        return Err(format!("{url} belongs to the WebUI"));
+    }
+    open_in_browser(&app_handle, &url);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expected() {
+        let page = |text: &str| Url::parse(&format!("data:text/html,{text}")).unwrap();
+        let mut expected = VecDeque::new();
+        for text in ["1", "2", "3"] {
+            expect(&mut expected, page(text));
+        }
+        assert!(take_expected(&mut expected, &page("1")));
+        // 2 was superseded before the webview got to it
+        assert!(take_expected(&mut expected, &page("3")));
+        assert!(!take_expected(&mut expected, &page("2")));
+        assert!(!take_expected(&mut expected, &page("1")));
+        for i in 0..=MAX_EXPECTED {
+            expect(&mut expected, page(&i.to_string()));
+        }
+        assert_eq!(MAX_EXPECTED, expected.len());
+        assert!(!take_expected(&mut expected, &page("0")));
+    }
+
+    #[test]
+    fn test_is_internal() {
+        let webui = Url::parse("http://127.0.0.1:22267/").unwrap();
+        let internal = |url: &str| is_internal(&Url::parse(url).unwrap(), &webui);
+        assert!(internal("http://127.0.0.1:22267/?app=alas"));
+        assert!(internal("about:blank"));
+        assert!(internal("data:text/html;base64,"));
+        assert!(internal("http://ipc.localhost/plugin:opener|open_url"));
+        assert!(!internal("http://127.0.0.1:8080/"));
+        assert!(!internal("http://localhost:22267/"));
+        assert!(!internal(
+            "https://github.com/LmeSzinc/AzurLaneAutoScript/issues"
+        ));
+        assert!(!internal("http://evil.localhost:22267/"));
+    }
+}
@@ -0,0 +1,59 @@
+use std::thread;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use tracing::{info, warn};
+
+use crate::{launcher::Launcher, settings_window::SettingsState};
+
+/// What a notification is about, each can be turned off in the settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    /// An ALAS task or instance finished
+    TaskFinished,
+    /// An error stopped an ALAS instance
+    Error,
+    /// The launcher failed to start or keep gui.py running
+    Launcher,
+    /// Warnings from the ALAS output, and anything else the WebUI sends through
+    /// `window.Notification`
+    Other,
+}
+
+/// Shows a desktop notification unless the settings turned `kind` off.
+pub fn send(app_handle: &AppHandle, kind: NotificationKind, title: &str, body: &str) {
+    let enabled = app_handle
+        .state::<SettingsState>()
+        .0
+        .lock()
+        .unwrap()
+        .notifications
+        .allows(kind);
+    if !enabled {
+        return;
+    }
+    let title = match app_handle.state::<Launcher>().profile() {
+        Some(profile) => format!("{title} ({profile})"),
+        None => title.to_owned(),
+    };
+    info!("Notification: {title}");
+    let body = body.to_owned();
+    // Showing can block on the notification server
+    thread::spawn(move || {
+        if let Err(e) = notify_rust::Notification::new()
+            .appname("Alas")
+            .summary(&title)
+            .body(&body)
+            .show()
+        {
+            warn!("Failed to show notification: {e}");
+        }
+    });
+}
+
+/// Lets the WebUI raise desktop notifications, see `alasLauncherNotify` in `page_load_injector`.
+#[tauri::command]
+pub fn notify(app_handle: AppHandle, kind: NotificationKind, title: &str, body: Option<&str>) {
+    send(&app_handle, kind, title, body.unwrap_or_default());
+}
@@ -0,0 +1,96 @@
+use std::process::Command;
+
+use anyhow::Result;
+use tauri::{AppHandle, Context, LogicalSize, Manager, RunEvent, Url};
+use tracing::{error, info};
+
+use crate::{escape_html, html_to_url, profile::Profile};
+
+/// Arguments to pass on to the launcher of the chosen profile.
+struct PickerArgs(Vec<String>);
+
+/// Shows the profile picker in the splash window, then restarts the launcher with the chosen
+/// profile, so each profile gets a launcher of its own.
+pub fn run(profiles: Vec<Profile>, args: Vec<String>, context: Context) -> Result<()> {
+    info!("Asking for a profile...");
+    tauri::Builder::default()
+        .manage(PickerArgs(args))
+        .invoke_handler(tauri::generate_handler![pick_profile])
+        .build(context)?
+        .run(move |app_handle, event| {
+            if let RunEvent::Ready = event {
+                let splash = app_handle.get_webview_window("splash").unwrap();
+                let height = 90.0 + 56.0 * profiles.len().min(8) as f64;
+                let _ = splash.set_title("Choose ALAS profile");
+                let _ = splash.set_size(LogicalSize::new(640.0, height));
+                let url = Url::parse(&picker_html(&profiles)).unwrap();
+                splash.navigate(url).unwrap();
+            }
+        });
+    Ok(())
+}
+
+#[tauri::command]
+fn pick_profile(app_handle: AppHandle, name: String) -> Result<(), String> {
+    let args = &app_handle.state::<PickerArgs>().0;
+    info!("Starting launcher for profile {name}");
+    let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+    if let Err(e) = Command::new(exe)
+        .arg("--profile")
+        .arg(&name)
+        .args(args)
+        .spawn()
+    {
+        error!("Failed to start launcher for profile {name}: {e}");
+        return Err(e.to_string());
+    }
+    app_handle.exit(0);
+    Ok(())
+}
+
+fn picker_html(profiles: &[Profile]) -> String {
+    let buttons = profiles
+        .iter()
+        .map(|profile| {
+            let detail = match (&profile.remote, &profile.repo) {
+                (Some(remote), _) => remote.clone(),
+                (None, Some(repo)) => repo.display().to_string(),
+                (None, None) => "ALAS folder of this launcher".to_owned(),
+            };
+            format!(
+                r#"<button data-name="{}"><b>{}</b><span>{}</span></button>"#,
+                escape_html(&profile.name),
+                escape_html(&profile.name),
+                escape_html(&detail)
+            )
+        })
+        .collect::<String>();
+    let html = format!(
+        r#"<!doctype html>
+<html>
+<head>
+<meta charset="utf-8">
+<style>
+  html,body{{margin:0;padding:0;background:#f6f8fa;color:#111;font-family:system-ui,-apple-system,Segoe UI,Roboto,"Helvetica Neue",Arial;}}
+  body{{padding:16px 20px;box-sizing:border-box;}}
+  p{{margin:0 0 12px;font-size:14px;}}
+  button{{display:flex;flex-direction:column;align-items:flex-start;width:100%;margin:0 0 8px;padding:8px 12px;border:1px solid #d0d7de;border-radius:6px;background:#fff;font:inherit;text-align:left;cursor:pointer;}}
+  button:hover{{background:#eef3f8;}}
+  span{{font-family:Menlo,monospace;font-size:12px;color:#57606a;word-break:break-all;}}
+</style>
+</head>
+<body>
+<p>Choose the ALAS to open</p>
+{buttons}
+<script>
+  document.querySelectorAll('button').forEach(button => button.addEventListener('click', () => {{
+    document.querySelectorAll('button').forEach(b => b.disabled = true);
+    window.__TAURI__.core.invoke('pick_profile', {{ name: button.dataset.name }})
+      .catch(() => document.querySelectorAll('button').forEach(b => b.disabled = false));
+  }}));
+</script>
+</body>
+</html>"#
+    );
+    html_to_url(&html)
+}
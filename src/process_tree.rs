@@ -0,0 +1,235 @@
+use std::{collections::HashMap, path::Path};
+
+use sysinfo::{Pid, ProcessRefreshKind, ProcessStatus, ProcessesToUpdate, System, UpdateKind};
+
+/// Descendants of the gui.py process, remembered by pid and start time.
+///
+/// Children are recorded while their parent is still alive, so they stay tracked after being
+/// reparented, and a reused pid is never mistaken for one of them.
+pub struct ProcessTree {
+    root: Option<Pid>,
+    system: System,
+    tracked: HashMap<Pid, TrackedProcess>,
+}
+
+struct TrackedProcess {
+    name: String,
+    start_time: u64,
+}
+
+impl ProcessTree {
+    pub fn new(root: u32) -> Self {
+        let mut tree = Self {
+            root: Some(Pid::from_u32(root)),
+            system: System::new(),
+            tracked: HashMap::new(),
+        };
+        tree.refresh();
+        tree
+    }
+
+    /// Tracks processes left behind by another launcher, together with their descendants.
+    pub fn adopt(leftovers: &[Leftover]) -> Self {
+        // The topmost leftover is usually the old gui.py
+        let root = leftovers
+            .iter()
+            .find(|l| !leftovers.iter().any(|p| Some(p.pid) == l.parent))
+            .map(|l| l.pid);
+        let tracked = leftovers
+            .iter()
+            .map(|l| {
+                let tracked = TrackedProcess {
+                    name: l.name.clone(),
+                    start_time: l.start_time,
+                };
+                (l.pid, tracked)
+            })
+            .collect();
+        let mut tree = Self {
+            root,
+            system: System::new(),
+            tracked,
+        };
+        tree.refresh();
+        tree
+    }
+
+    pub fn root(&self) -> Option<u32> {
+        self.root.map(|pid| pid.as_u32())
+    }
+
+    pub fn refresh(&mut self) {
+        self.system.refresh_processes_specifics(
+            ProcessesToUpdate::All,
+            true,
+            ProcessRefreshKind::nothing().without_tasks(),
+        );
+        let system = &self.system;
+        self.tracked.retain(|pid, tracked| {
+            system
+                .process(*pid)
+                .is_some_and(|p| p.start_time() == tracked.start_time)
+        });
+        loop {
+            let found = self
+                .system
+                .processes()
+                .iter()
+                .filter(|(pid, process)| {
+                    Some(**pid) != self.root
+                        && !self.tracked.contains_key(pid)
+                        && process.thread_kind().is_none()
+                        && (process.parent().is_some_and(|ppid| {
+                            Some(ppid) == self.root || self.tracked.contains_key(&ppid)
+                        }) || self.root.is_some_and(|root| in_process_group(**pid, root)))
+                })
+                .map(|(pid, process)| {
+                    let tracked = TrackedProcess {
+                        name: process.name().to_string_lossy().into_owned(),
+                        start_time: process.start_time(),
+                    };
+                    (*pid, tracked)
+                })
+                .collect::<Vec<_>>();
+            if found.is_empty() {
+                break;
+            }
+            self.tracked.extend(found);
+        }
+    }
+
+    /// Whether the tracked process `pid` is still running.
+    pub fn is_alive(&mut self, pid: u32) -> bool {
+        let pid = Pid::from_u32(pid);
+        let Some(tracked) = self.tracked.get(&pid) else {
+            return false;
+        };
+        self.system.refresh_processes_specifics(
+            ProcessesToUpdate::Some(&[pid]),
+            true,
+            ProcessRefreshKind::nothing().without_tasks(),
+        );
+        self.system.process(pid).is_some_and(|p| {
+            p.start_time() == tracked.start_time && p.status() != ProcessStatus::Zombie
+        })
+    }
+
+    /// Kills every tracked process that is still alive, returning "pid (name)" of each one killed.
+    pub fn kill_all(&mut self) -> Vec<String> {
+        self.refresh();
+        let mut killed = Vec::new();
+        for (pid, tracked) in self.tracked.drain() {
+            if let Some(process) = self.system.process(pid) {
+                if process.status() != ProcessStatus::Zombie && process.kill() {
+                    killed.push(format!("{pid} ({})", tracked.name));
+                }
+            }
+        }
+        killed
+    }
+}
+
+/// A process started by a launcher that is no longer running, found through `ALAS_LAUNCHER_PID`.
+#[derive(Clone)]
+pub struct Leftover {
+    pub pid: Pid,
+    pub name: String,
+    pub launcher_pid: u32,
+    parent: Option<Pid>,
+    start_time: u64,
+}
+
+/// Scans every process environment for an `ALAS_LAUNCHER_PID` whose launcher has exited,
+/// skipping processes started for another ALAS folder.
+pub fn find_leftovers() -> Vec<Leftover> {
+    let mut system = System::new();
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::All,
+        true,
+        ProcessRefreshKind::nothing()
+            .without_tasks()
+            .with_environ(UpdateKind::Always),
+    );
+    let own_pid = Pid::from_u32(std::process::id());
+    let own_repo = std::env::current_dir().unwrap_or_default();
+    let mut leftovers = system
+        .processes()
+        .iter()
+        .filter(|(pid, process)| {
+            **pid != own_pid
+                && process.thread_kind().is_none()
+                && process.status() != ProcessStatus::Zombie
+        })
+        .filter_map(|(pid, process)| {
+            let launcher_pid = process.environ().iter().find_map(|var| {
+                var.to_str()?
+                    .strip_prefix("ALAS_LAUNCHER_PID=")?
+                    .parse::<u32>()
+                    .ok()
+            })?;
+            // Launchers from before profiles didn't set the repo
+            let repo = process
+                .environ()
+                .iter()
+                .find_map(|var| var.to_str()?.strip_prefix("ALAS_LAUNCHER_REPO="));
+            if repo.is_some_and(|repo| Path::new(repo) != own_repo) {
+                return None;
+            }
+            (!is_launcher(&system, Pid::from_u32(launcher_pid))).then(|| Leftover {
+                pid: *pid,
+                name: process.name().to_string_lossy().into_owned(),
+                launcher_pid,
+                parent: process.parent(),
+                start_time: process.start_time(),
+            })
+        })
+        .collect::<Vec<_>>();
+    leftovers.sort_by_key(|l| l.pid);
+    leftovers
+}
+
+fn is_launcher(system: &System, pid: Pid) -> bool {
+    let own_name = system
+        .process(Pid::from_u32(std::process::id()))
+        .map(|p| p.name());
+    system
+        .process(pid)
+        .is_some_and(|p| Some(p.name()) == own_name)
+}
+
+#[cfg(unix)]
+fn in_process_group(pid: Pid, pgid: Pid) -> bool {
+    use nix::unistd::{getpgid, Pid as NixPid};
+    getpgid(Some(NixPid::from_raw(pid.as_u32() as i32)))
+        .is_ok_and(|group| group.as_raw() as u32 == pgid.as_u32())
+}
+
+// Windows has no process groups, gui.py's job object already covers its descendants
+#[cfg(not(unix))]
+fn in_process_group(_pid: Pid, _pgid: Pid) -> bool {
+    false
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::{process::Command, thread, time::Duration};
+
+    #[test]
+    fn test_process_tree() {
+        // The shell may exec into sleep right away, so start two of them
+        let mut child = Command::new("sh")
+            .args(["-c", "sleep 30 & sleep 30; wait"])
+            .spawn()
+            .unwrap();
+        thread::sleep(Duration::from_millis(300));
+        let mut tree = ProcessTree::new(child.id());
+        assert_eq!(Some(child.id()), tree.root());
+        let killed = tree.kill_all();
+        assert!(!killed.is_empty());
+        assert!(killed.iter().all(|process| process.contains("sleep")));
+        let _ = child.kill();
+        child.wait().unwrap();
+        assert!(tree.kill_all().is_empty());
+    }
+}
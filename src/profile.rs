@@ -0,0 +1,167 @@
+use std::{fs, io::ErrorKind, path::PathBuf};
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+/// One ALAS installation the launcher can run, or a remote one it can connect to.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Profile {
+    pub name: String,
+    /// ALAS folder, the one next to the launcher if not set
+    #[serde(default)]
+    pub repo: Option<PathBuf>,
+    /// Overrides `Deploy.Webui.WebuiPort`
+    #[serde(default)]
+    pub port: Option<u16>,
+    /// Whether ALAS is updated when the launcher starts
+    #[serde(default = "default_update")]
+    pub update: bool,
+    /// URL of a remote WebUI, `repo` and `port` are ignored if set
+    #[serde(default)]
+    pub remote: Option<String>,
+    /// Password of the remote WebUI
+    #[serde(default)]
+    pub password: Option<String>,
+}
+
+fn default_update() -> bool {
+    true
+}
+
+/// `profiles.yaml` in the launcher's config folder.
+///
+/// ```yaml
+/// profiles:
+///   - name: stable
+///     repo: /home/me/AzurLaneAutoScript
+///   - name: dev
+///     repo: /home/me/alas-dev
+///     port: 22268
+///     update: false
+///   - name: server
+///     remote: http://192.168.1.2:22267/
+/// ```
+#[derive(Debug, Default, Deserialize)]
+pub struct Profiles {
+    #[serde(default)]
+    pub profiles: Vec<Profile>,
+}
+
+/// Which profile a launcher should use.
+#[derive(Debug, PartialEq)]
+pub enum Choice<'a> {
+    /// No profiles are configured, use the ALAS folder next to the launcher
+    Default,
+    Profile(&'a Profile),
+    /// Several profiles and none given, ask the user
+    Ask,
+}
+
+impl Profiles {
+    pub fn path() -> Option<PathBuf> {
+        Some(
+            dirs::config_dir()?
+                .join("alas-launcher")
+                .join("profiles.yaml"),
+        )
+    }
+
+    pub fn load() -> Result<Self> {
+        let Some(path) = Self::path() else {
+            return Ok(Self::default());
+        };
+        match fs::read_to_string(&path) {
+            Ok(content) => Self::parse(&content)
+                .map_err(|e| anyhow!("Invalid profiles in {}: {e}", path.display())),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(anyhow!("Failed to read {}: {e}", path.display())),
+        }
+    }
+
+    fn parse(content: &str) -> Result<Self> {
+        let profiles: Self = serde_yaml::from_str(content)?;
+        for (i, profile) in profiles.profiles.iter().enumerate() {
+            // The name is part of the control socket path
+            if profile.name.is_empty()
+                || !profile
+                    .name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            {
+                return Err(anyhow!(
+                    "Profile name {:?} must only have letters, digits, - and _",
+                    profile.name
+                ));
+            }
+            if profiles.profiles[..i]
+                .iter()
+                .any(|p| p.name == profile.name)
+            {
+                return Err(anyhow!("Duplicate profile {:?}", profile.name));
+            }
+        }
+        Ok(profiles)
+    }
+
+    pub fn choose(&self, name: Option<&str>) -> Result<Choice<'_>> {
+        match (name, self.profiles.as_slice()) {
+            (Some(name), profiles) => profiles
+                .iter()
+                .find(|p| p.name == name)
+                .map(Choice::Profile)
+                .ok_or_else(|| {
+                    let names = profiles.iter().map(|p| p.name.as_str()).collect::<Vec<_>>();
+                    anyhow!(
+                        "Profile {name:?} not found, available profiles: {}",
+                        names.join(", ")
+                    )
+                }),
+            (None, []) => Ok(Choice::Default),
+            (None, [only]) => Ok(Choice::Profile(only)),
+            (None, _) => Ok(Choice::Ask),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_choose_profile() {
+        let profiles = Profiles::parse(
+            r#"
+profiles:
+  - name: stable
+    repo: /opt/alas
+  - name: server
+    remote: http://192.168.1.2:22267/
+    update: false
+"#,
+        )
+        .unwrap();
+        let Ok(Choice::Profile(server)) = profiles.choose(Some("server")) else {
+            panic!("server not chosen");
+        };
+        assert_eq!(Some("http://192.168.1.2:22267/"), server.remote.as_deref());
+        assert!(!server.update);
+        assert_eq!(Choice::Ask, profiles.choose(None).unwrap());
+        assert!(profiles.choose(Some("dev")).is_err());
+        assert_eq!(
+            Choice::Default,
+            Profiles::parse("").unwrap().choose(None).unwrap()
+        );
+        let single = Profiles::parse("profiles: [{name: dev}]").unwrap();
+        let Ok(Choice::Profile(dev)) = single.choose(None) else {
+            panic!("dev not chosen");
+        };
+        assert!(dev.update);
+    }
+
+    #[test]
+    fn test_invalid_profiles() {
+        assert!(Profiles::parse("profiles: [{name: a}, {name: a}]").is_err());
+        assert!(Profiles::parse("profiles: [{name: ../a}]").is_err());
+        assert!(Profiles::parse("profiles: [{repo: /opt/alas}]").is_err());
+    }
+}
@@ -0,0 +1,153 @@
+use std::{
+    fs::{self, File, OpenOptions, TryLockError},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+const LOCK_FILE: &str = "./config/alas-launcher.lock";
+
+/// Lock on a file in the ALAS repo, so two launchers never update or run the same checkout.
+///
+/// The OS releases it when the launcher exits, however it exits. Who holds it is written next to
+/// it, only to tell the user.
+pub struct RepoLock {
+    // Locked while open
+    _file: File,
+    holder_path: PathBuf,
+}
+
+#[derive(Serialize, Deserialize)]
+struct LockHolder {
+    pid: u32,
+    port: u16,
+    started: u64,
+    exe: PathBuf,
+}
+
+impl RepoLock {
+    pub fn acquire(port: u16) -> Result<Self> {
+        fs::create_dir_all("./config")?;
+        Self::acquire_at(Path::new(LOCK_FILE), port)
+    }
+
+    fn acquire_at(path: &Path, port: u16) -> Result<Self> {
+        // Windows doesn't let others read a locked file, so the holder has a file of its own
+        let holder_path = path.with_extension("lock.json");
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(path)?;
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                let mut message =
+                    "This ALAS folder is already in use by another alas-launcher".to_owned();
+                if let Some(other) = read_holder(&holder_path) {
+                    message += &format!(
+                        "\n\nPID {}, WebUI port {}, started {} ago\n{}",
+                        other.pid,
+                        other.port,
+                        format_elapsed(other.started),
+                        other.exe.display()
+                    );
+                }
+                return Err(anyhow!(message));
+            }
+            Err(TryLockError::Error(e)) => {
+                return Err(anyhow!("Failed to lock {}: {e}", path.display()));
+            }
+        }
+        info!("Locked ALAS repo with {}", path.display());
+        let holder = LockHolder {
+            pid: std::process::id(),
+            port,
+            started: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            exe: std::env::current_exe().unwrap_or_default(),
+        };
+        if let Err(e) = write_holder(&holder_path, &holder) {
+            warn!("Failed to write {}: {e}", holder_path.display());
+        }
+        Ok(Self {
+            _file: file,
+            holder_path,
+        })
+    }
+}
+
+impl Drop for RepoLock {
+    fn drop(&mut self) {
+        // The lock file itself stays, removing it would let the next two launchers lock
+        // different files
+        if let Err(e) = fs::remove_file(&self.holder_path) {
+            warn!("Failed to remove {}: {e}", self.holder_path.display());
+        }
+    }
+}
+
+// Renamed into place fully written, so others never read it half done
+fn write_holder(path: &Path, holder: &LockHolder) -> Result<()> {
+    let temp_path = path.with_extension(format!("json.{}", holder.pid));
+    fs::write(&temp_path, serde_json::to_string(holder)?)?;
+    fs::rename(&temp_path, path).inspect_err(|_| {
+        let _ = fs::remove_file(&temp_path);
+    })?;
+    Ok(())
+}
+
+fn read_holder(path: &Path) -> Option<LockHolder> {
+    let content = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn format_elapsed(since: u64) -> String {
+    let started = UNIX_EPOCH + Duration::from_secs(since);
+    let secs = SystemTime::now()
+        .duration_since(started)
+        .unwrap_or_default()
+        .as_secs();
+    match secs {
+        0..60 => format!("{secs}s"),
+        60..3600 => format!("{}m", secs / 60),
+        _ => format!("{}h {}m", secs / 3600, secs % 3600 / 60),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repo_lock() {
+        let dir = std::env::temp_dir().join(format!("alas-launcher-lock-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("alas-launcher.lock");
+        let holder_path = dir.join("alas-launcher.lock.json");
+
+        // Left by a launcher that crashed, it's no longer locked
+        fs::write(&holder_path, "").unwrap();
+        let lock = RepoLock::acquire_at(&path, 1).unwrap();
+        assert_eq!(Some(1), read_holder(&holder_path).map(|holder| holder.port));
+
+        // Locks are per open file, so a second open in this process stands in for another launcher
+        let Err(error) = RepoLock::acquire_at(&path, 2) else {
+            panic!("Locked twice");
+        };
+        let error = error.to_string();
+        assert!(error.contains("WebUI port 1"), "{error}");
+        assert_eq!(Some(1), read_holder(&holder_path).map(|holder| holder.port));
+
+        drop(lock);
+        assert!(!holder_path.exists());
+        drop(RepoLock::acquire_at(&path, 2).unwrap());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
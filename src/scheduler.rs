@@ -0,0 +1,90 @@
+use std::{thread, time::Duration};
+
+use chrono::{Days, Local, NaiveDateTime, NaiveTime, TimeZone};
+use tracing::{error, info};
+
+use crate::{host::Host, settings::ScheduleSettings, state::LauncherState};
+
+/// Checking the clock this often notices suspends and clock changes soon enough
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// First time after `now` that is `at` o'clock.
+pub fn next_run(now: NaiveDateTime, at: NaiveTime) -> NaiveDateTime {
+    let today = now.date().and_time(at);
+    if today > now {
+        today
+    } else {
+        today + Days::new(1)
+    }
+}
+
+/// Restarts gui.py every day at the configured time, updating ALAS first unless turned off.
+pub fn start(host: impl Host, schedule: &ScheduleSettings) {
+    let Some(at) = schedule.restart_time() else {
+        return;
+    };
+    let update = schedule.update;
+    info!("Scheduled a daily restart at {}", at.format("%H:%M"));
+    thread::spawn(move || loop {
+        let next = next_run(Local::now().naive_local(), at);
+        // The clock may jump meanwhile, so sleep in short steps
+        while Local::now().naive_local() < next {
+            thread::sleep(POLL_INTERVAL);
+        }
+        // Skip times that don't exist on this day, e.g. when the clock is set forward
+        if Local.from_local_datetime(&next).earliest().is_none() {
+            continue;
+        }
+        run(&host, update);
+    });
+}
+
+fn run(host: &impl Host, update: bool) {
+    let launcher = host.launcher();
+    if launcher.is_remote() {
+        return;
+    }
+    match launcher.state().get() {
+        LauncherState::Stopped | LauncherState::ShuttingDown => {
+            info!("Skipping the scheduled restart, ALAS is stopped");
+            return;
+        }
+        _ if launcher.is_busy() => {
+            info!("Skipping the scheduled restart, ALAS is already starting or restarting");
+            return;
+        }
+        _ => {}
+    }
+    info!(
+        "Scheduled restart{}, running instances {:?}",
+        if update { " and update" } else { "" },
+        launcher.status().instances
+    );
+    match host.restart(update) {
+        Ok(()) => info!("Scheduled restart finished"),
+        Err(e) => error!("Scheduled restart failed: {e}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_run() {
+        let at = NaiveTime::from_hms_opt(3, 50, 0).unwrap();
+        let time = |s: &str| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").unwrap();
+        assert_eq!(
+            time("2024-05-01 03:50:00"),
+            next_run(time("2024-05-01 01:00:00"), at)
+        );
+        assert_eq!(
+            time("2024-05-02 03:50:00"),
+            next_run(time("2024-05-01 03:50:00"), at)
+        );
+        assert_eq!(
+            time("2024-06-01 03:50:00"),
+            next_run(time("2024-05-31 23:59:59"), at)
+        );
+    }
+}
@@ -0,0 +1,218 @@
+use std::{
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::{anyhow, Result};
+use tracing::{info, warn};
+
+use crate::{
+    cli::ServiceCommand,
+    profile::Profile,
+    settings::Settings,
+    setup::{alas_repo_dir, setup_environment},
+};
+
+/// Variables `setup_environment` changes, passed on to the service.
+const ENVIRONMENT: &[&str] = &["PATH", "LD_LIBRARY_PATH"];
+
+fn unit_name(profile: Option<&str>) -> String {
+    match profile {
+        Some(profile) => format!("alas-launcher-{profile}.service"),
+        None => "alas-launcher.service".to_owned(),
+    }
+}
+
+fn unit_path(name: &str) -> Result<PathBuf> {
+    let dir = dirs::config_dir().ok_or_else(|| anyhow!("No config folder on this system"))?;
+    Ok(dir.join("systemd").join("user").join(name))
+}
+
+/// Escapes `%` specifiers, which systemd expands in most settings.
+fn escape(value: &str) -> String {
+    value.replace('%', "%%")
+}
+
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Quotes an `ExecStart` argument, where `$` would expand a variable.
+fn exec_arg(arg: &str) -> String {
+    quote(&escape(arg).replace('$', "$$"))
+}
+
+/// A `systemd --user` unit running `args` headless in `repo`.
+pub fn unit_content(
+    description: &str,
+    args: &[String],
+    repo: &Path,
+    environment: &[(String, String)],
+    stop_timeout: u64,
+) -> String {
+    let exec = args
+        .iter()
+        .map(|arg| exec_arg(arg))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let environment = environment
+        .iter()
+        .map(|(key, value)| {
+            format!(
+                "Environment={}\n",
+                quote(&escape(&format!("{key}={value}")))
+            )
+        })
+        .collect::<String>();
+    format!(
+        "[Unit]
+Description={}
+After=network-online.target
+Wants=network-online.target
+
+[Service]
+Type=simple
+ExecStart={exec}
+WorkingDirectory={}
+{environment}Restart=on-failure
+RestartSec=10
+# SIGTERM lets the launcher stop gui.py gracefully, leftovers are killed after the timeout
+KillMode=mixed
+TimeoutStopSec={stop_timeout}
+StandardOutput=journal
+StandardError=journal
+SyslogIdentifier=alas-launcher
+
+[Install]
+WantedBy=default.target
+",
+        escape(description),
+        escape(&repo.to_string_lossy()),
+    )
+}
+
+fn systemctl(args: &[&str]) -> Result<()> {
+    let status = Command::new("systemctl")
+        .arg("--user")
+        .args(args)
+        .status()?;
+    if !status.success() {
+        return Err(anyhow!(
+            "systemctl --user {} failed with {status}",
+            args.join(" ")
+        ));
+    }
+    Ok(())
+}
+
+fn install(profile: Option<&Profile>, settings: &Settings) -> Result<()> {
+    if profile.is_some_and(|p| p.remote.is_some()) {
+        return Err(anyhow!("A remote profile has no ALAS to run as a service"));
+    }
+    let repo = match profile.and_then(|p| p.repo.clone()) {
+        Some(repo) => repo,
+        None => alas_repo_dir(),
+    };
+    setup_environment(Some(&repo))?;
+    let environment = ENVIRONMENT
+        .iter()
+        .filter_map(|key| Some((key.to_string(), std::env::var(key).ok()?)))
+        .collect::<Vec<_>>();
+    let mut args = vec![std::env::current_exe()?.to_string_lossy().into_owned()];
+    let description = match profile {
+        Some(profile) => {
+            args.extend(["--profile".to_owned(), profile.name.clone()]);
+            format!("Alas Launcher ({})", profile.name)
+        }
+        None => "Alas Launcher".to_owned(),
+    };
+    args.push("--headless".to_owned());
+    let stop_timeout = (settings.shutdown_grace + settings.term_grace).ceil() as u64 + 10;
+    let name = unit_name(profile.map(|p| p.name.as_str()));
+    let path = unit_path(&name)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let content = unit_content(&description, &args, &repo, &environment, stop_timeout);
+    fs::write(&path, content)?;
+    info!("Wrote {}", path.display());
+    systemctl(&["daemon-reload"])?;
+    systemctl(&["enable", &name])?;
+    println!("Installed {name}, start it with:\n\n  systemctl --user start {name}\n");
+    println!("To keep it running while you're logged out:\n\n  loginctl enable-linger\n");
+    println!("Its output is in: journalctl --user -u {name}");
+    Ok(())
+}
+
+fn uninstall(profile: Option<&Profile>) -> Result<()> {
+    let name = unit_name(profile.map(|p| p.name.as_str()));
+    let path = unit_path(&name)?;
+    if !path.exists() {
+        return Err(anyhow!("{name} is not installed"));
+    }
+    if let Err(e) = systemctl(&["disable", "--now", &name]) {
+        warn!("{e}");
+    }
+    match fs::remove_file(&path) {
+        Ok(()) => {}
+        Err(e) if e.kind() == ErrorKind::NotFound => {}
+        Err(e) => return Err(anyhow!("Failed to remove {}: {e}", path.display())),
+    }
+    systemctl(&["daemon-reload"])?;
+    println!("Uninstalled {name}");
+    Ok(())
+}
+
+/// Runs `alas-launcher install-service` or `uninstall-service` and exits.
+pub fn run_cli(profile: Option<&Profile>, command: ServiceCommand, settings: &Settings) -> ! {
+    let result = if !cfg!(target_os = "linux") {
+        Err(anyhow!("Services are only supported on Linux with systemd"))
+    } else {
+        match command {
+            ServiceCommand::Install => install(profile, settings),
+            ServiceCommand::Uninstall => uninstall(profile),
+        }
+    };
+    match result {
+        Ok(()) => std::process::exit(0),
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unit_content() {
+        let unit = unit_content(
+            "Alas Launcher (main)",
+            &[
+                "/opt/alas/alas-launcher".to_owned(),
+                "--profile".to_owned(),
+                "main".to_owned(),
+                "--headless".to_owned(),
+            ],
+            Path::new("/home/me/Alas 100%"),
+            &[(
+                "PATH".to_owned(),
+                "/home/me/Alas/toolkit/bin:/usr/bin".to_owned(),
+            )],
+            23,
+        );
+        assert!(unit.contains(
+            "ExecStart=\"/opt/alas/alas-launcher\" \"--profile\" \"main\" \"--headless\"\n"
+        ));
+        assert!(unit.contains("WorkingDirectory=/home/me/Alas 100%%\n"));
+        assert!(unit.contains("Environment=\"PATH=/home/me/Alas/toolkit/bin:/usr/bin\"\n"));
+        assert!(unit.contains("Restart=on-failure\n"));
+        assert!(unit.contains("TimeoutStopSec=23\n"));
+        assert_eq!(r#""a\"b $$HOME 1%%""#, exec_arg(r#"a"b $HOME 1%"#));
+        assert_eq!(r#""A=\\ $HOME""#, quote(r"A=\ $HOME"));
+    }
+}
@@ -0,0 +1,328 @@
+use std::{fs, io::ErrorKind, path::PathBuf, str::FromStr, time::Duration};
+
+use anyhow::{anyhow, Result};
+use chrono::NaiveTime;
+use serde::{Deserialize, Serialize};
+use tracing::Level;
+
+use crate::{
+    log_watch::{default_rules, LogRule, RuleSet},
+    notify::NotificationKind,
+};
+
+/// Upper bound of the shutdown grace periods, in seconds
+pub const MAX_GRACE_SECS: f64 = 600.0;
+
+/// `launcher.yaml` in the launcher's config folder, next to `profiles.yaml`.
+///
+/// Every field is optional, e.g. `tray: {enabled: true}` keeps the other defaults.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    /// Seconds to wait for gui.py to accept connections
+    pub startup_timeout: u64,
+    /// Seconds gui.py gets to exit after SIGINT, then after SIGTERM
+    pub shutdown_grace: f64,
+    pub term_grace: f64,
+    pub update: UpdatePolicy,
+    pub tray: TraySettings,
+    /// One of error, warn, info, debug, trace
+    pub log_level: String,
+    pub window: WindowSettings,
+    pub notifications: NotificationSettings,
+    /// Alerts raised from the output of gui.py, the built-in rules if not set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_rules: Option<Vec<LogRule>>,
+    pub schedule: ScheduleSettings,
+    pub watchdog: WatchdogSettings,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UpdatePolicy {
+    /// Update ALAS every time the launcher starts, unless the profile says otherwise
+    OnStart,
+    /// Only update when asked to
+    Manual,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TraySettings {
+    pub enabled: bool,
+    /// Hide the window instead of quitting when it's closed
+    pub close_to_tray: bool,
+}
+
+/// Desktop notifications, on or off for each kind
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NotificationSettings {
+    pub enabled: bool,
+    pub task_finished: bool,
+    pub error: bool,
+    pub launcher: bool,
+    pub other: bool,
+}
+
+/// Daily restart of gui.py, in case it hangs where its own `AutoRestartTime` can't help
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScheduleSettings {
+    /// Local time as `HH:MM`, no daily restart if not set
+    pub restart_at: Option<String>,
+    /// Whether ALAS is updated before restarting
+    pub update: bool,
+}
+
+/// Health probes of the WebUI, restarting gui.py when it stops answering without exiting
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WatchdogSettings {
+    pub enabled: bool,
+    /// Seconds between probes
+    pub interval: u64,
+    /// Seconds a probe waits for an answer
+    pub timeout: u64,
+    /// Failed probes in a row before gui.py is restarted
+    pub failures: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WindowSettings {
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            startup_timeout: 60,
+            shutdown_grace: 10.0,
+            term_grace: 3.0,
+            update: UpdatePolicy::OnStart,
+            tray: TraySettings::default(),
+            log_level: "info".to_owned(),
+            window: WindowSettings::default(),
+            notifications: NotificationSettings::default(),
+            log_rules: None,
+            schedule: ScheduleSettings::default(),
+            watchdog: WatchdogSettings::default(),
+        }
+    }
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            task_finished: true,
+            error: true,
+            launcher: true,
+            other: true,
+        }
+    }
+}
+
+impl Default for ScheduleSettings {
+    fn default() -> Self {
+        Self {
+            restart_at: None,
+            update: true,
+        }
+    }
+}
+
+impl Default for WatchdogSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval: 30,
+            timeout: 10,
+            failures: 3,
+        }
+    }
+}
+
+impl WatchdogSettings {
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval)
+    }
+}
+
+impl ScheduleSettings {
+    pub fn restart_time(&self) -> Option<NaiveTime> {
+        NaiveTime::parse_from_str(self.restart_at.as_deref()?, "%H:%M").ok()
+    }
+}
+
+impl NotificationSettings {
+    pub fn allows(&self, kind: NotificationKind) -> bool {
+        self.enabled
+            && match kind {
+                NotificationKind::TaskFinished => self.task_finished,
+                NotificationKind::Error => self.error,
+                NotificationKind::Launcher => self.launcher,
+                NotificationKind::Other => self.other,
+            }
+    }
+}
+
+impl Default for WindowSettings {
+    fn default() -> Self {
+        Self {
+            width: 1280.0,
+            height: 880.0,
+        }
+    }
+}
+
+impl Settings {
+    pub fn path() -> Option<PathBuf> {
+        Some(
+            dirs::config_dir()?
+                .join("alas-launcher")
+                .join("launcher.yaml"),
+        )
+    }
+
+    pub fn load() -> Result<Self> {
+        let Some(path) = Self::path() else {
+            return Ok(Self::default());
+        };
+        match fs::read_to_string(&path) {
+            Ok(content) => Self::parse(&content)
+                .map_err(|e| anyhow!("Invalid settings in {}: {e}", path.display())),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(anyhow!("Failed to read {}: {e}", path.display())),
+        }
+    }
+
+    fn parse(content: &str) -> Result<Self> {
+        let settings: Self = serde_yaml::from_str(content)?;
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        self.validate()?;
+        let path = Self::path().ok_or_else(|| anyhow!("No config folder on this system"))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&path, serde_yaml::to_string(self)?)?;
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if !(1..=3600).contains(&self.startup_timeout) {
+            return Err(anyhow!("startup_timeout must be 1 to 3600 seconds"));
+        }
+        for (name, secs) in [
+            ("shutdown_grace", self.shutdown_grace),
+            ("term_grace", self.term_grace),
+        ] {
+            if !(0.0..=MAX_GRACE_SECS).contains(&secs) {
+                return Err(anyhow!("{name} must be 0 to {MAX_GRACE_SECS} seconds"));
+            }
+        }
+        Level::from_str(&self.log_level)
+            .map_err(|_| anyhow!("Unknown log_level {:?}", self.log_level))?;
+        if !(400.0..=10000.0).contains(&self.window.width)
+            || !(300.0..=10000.0).contains(&self.window.height)
+        {
+            return Err(anyhow!("Window size must be at least 400x300"));
+        }
+        if let Some(restart_at) = &self.schedule.restart_at {
+            if self.schedule.restart_time().is_none() {
+                return Err(anyhow!(
+                    "Invalid restart time {restart_at:?}, expected HH:MM"
+                ));
+            }
+        }
+        if !(5..=3600).contains(&self.watchdog.interval) {
+            return Err(anyhow!("watchdog.interval must be 5 to 3600 seconds"));
+        }
+        if !(1..=self.watchdog.interval).contains(&self.watchdog.timeout) {
+            return Err(anyhow!(
+                "watchdog.timeout must be 1 second to watchdog.interval"
+            ));
+        }
+        if !(1..=100).contains(&self.watchdog.failures) {
+            return Err(anyhow!("watchdog.failures must be 1 to 100"));
+        }
+        if let Some(rules) = &self.log_rules {
+            RuleSet::new(rules)?;
+        }
+        Ok(())
+    }
+
+    pub fn startup_timeout(&self) -> Duration {
+        Duration::from_secs(self.startup_timeout)
+    }
+
+    pub fn log_level(&self) -> Level {
+        Level::from_str(&self.log_level).unwrap_or(Level::INFO)
+    }
+
+    pub fn log_rules(&self) -> Vec<LogRule> {
+        self.log_rules.clone().unwrap_or_else(default_rules)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_settings() {
+        assert_eq!(Settings::default(), Settings::parse("").unwrap());
+        let settings = Settings::parse(
+            r#"
+startup_timeout: 120
+update: manual
+tray: {enabled: true}
+log_level: debug
+notifications: {task_finished: false}
+schedule: {restart_at: "03:50"}
+watchdog: {failures: 5}
+"#,
+        )
+        .unwrap();
+        assert_eq!(Duration::from_secs(120), settings.startup_timeout());
+        assert_eq!(UpdatePolicy::Manual, settings.update);
+        assert!(settings.tray.enabled);
+        assert!(!settings.tray.close_to_tray);
+        assert_eq!(Level::DEBUG, settings.log_level());
+        assert_eq!(10.0, settings.shutdown_grace);
+        assert!(!settings
+            .notifications
+            .allows(NotificationKind::TaskFinished));
+        assert!(settings.notifications.allows(NotificationKind::Error));
+        assert_eq!(
+            NaiveTime::from_hms_opt(3, 50, 0),
+            settings.schedule.restart_time()
+        );
+        assert!(settings.schedule.update);
+        assert!(settings.watchdog.enabled);
+        assert_eq!(5, settings.watchdog.failures);
+        assert_eq!(30, settings.watchdog.interval);
+    }
+
+    #[test]
+    fn test_invalid_settings() {
+        assert!(Settings::parse("startup_timeout: 0").is_err());
+        assert!(Settings::parse("shutdown_grace: -1").is_err());
+        assert!(Settings::parse("log_level: loud").is_err());
+        assert!(Settings::parse("update: sometimes").is_err());
+        assert!(Settings::parse("window: {width: 10}").is_err());
+        assert!(Settings::parse("schedule: {restart_at: '25:00'}").is_err());
+        assert!(Settings::parse("watchdog: {interval: 10, timeout: 20}").is_err());
+        assert!(Settings::parse("watchdog: {failures: 0}").is_err());
+        assert!(Settings::parse("startup_timout: 10").is_err());
+        assert!(
+            Settings::parse("log_rules: [{pattern: '(', severity: error, message: x}]").is_err()
+        );
+    }
+}
@@ -0,0 +1,177 @@
+use std::sync::Mutex;
+
+use tauri::{AppHandle, Manager, Url, Webview, WebviewUrl, WebviewWindowBuilder};
+use tracing::{info, warn};
+
+use crate::{html_to_url, settings::Settings};
+
+/// Settings as saved in `launcher.yaml`. Only `tray.close_to_tray` and `notifications` apply
+/// right away, everything else is read once at startup.
+pub struct SettingsState(pub Mutex<Settings>);
+
+#[tauri::command]
+pub fn get_settings(app_handle: AppHandle) -> Settings {
+    app_handle
+        .state::<SettingsState>()
+        .0
+        .lock()
+        .unwrap()
+        .clone()
+}
+
+/// App commands are open to every page, but the main window may show pages from anywhere,
+/// e.g. a remote WebUI, so only the settings window can change settings.
+pub fn check_caller(webview: &Webview) -> Result<(), String> {
+    if webview.label() == "settings" {
+        return Ok(());
+    }
+    warn!("Rejected a settings change from window {}", webview.label());
+    Err("Settings can only be changed in the settings window".to_owned())
+}
+
+#[tauri::command]
+pub fn save_settings(
+    app_handle: AppHandle,
+    webview: Webview,
+    settings: Settings,
+) -> Result<(), String> {
+    check_caller(&webview)?;
+    settings.save().map_err(|e| e.to_string())?;
+    info!("Saved launcher settings");
+    *app_handle.state::<SettingsState>().0.lock().unwrap() = settings;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn open_settings(app_handle: AppHandle) {
+    open(&app_handle);
+}
+
+pub fn open(app_handle: &AppHandle) {
+    if let Some(window) = app_handle.get_webview_window("settings") {
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+        return;
+    }
+    let url = Url::parse(&html_to_url(SETTINGS_HTML)).unwrap();
+    if let Err(e) = WebviewWindowBuilder::new(app_handle, "settings", WebviewUrl::External(url))
+        .title("Alas Launcher Settings")
+        .inner_size(520.0, 600.0)
+        .build()
+    {
+        warn!("Failed to open settings window: {e}");
+    }
+}
+
+const SETTINGS_HTML: &str = r#"<!doctype html>
+<html>
+<head>
+<meta charset="utf-8">
+<style>
+  html,body{margin:0;padding:0;background:#f6f8fa;color:#111;font-family:system-ui,-apple-system,Segoe UI,Roboto,"Helvetica Neue",Arial;font-size:14px;}
+  form{padding:16px 20px;}
+  fieldset{border:1px solid #d0d7de;border-radius:6px;margin:0 0 12px;padding:8px 12px;}
+  legend{font-weight:600;}
+  label{display:flex;justify-content:space-between;align-items:center;margin:6px 0;}
+  input[type=number],select{width:120px;}
+  p{margin:8px 0;color:#57606a;}
+  #message{min-height:1.5em;}
+  #message.error{color:#cf222e;}
+</style>
+</head>
+<body>
+<form id="settings">
+  <fieldset>
+    <legend>ALAS</legend>
+    <label>Update on start
+      <select name="update"><option value="on_start">Always</option><option value="manual">Never</option></select></label>
+    <label>Startup timeout (s) <input type="number" name="startup_timeout" min="1" max="3600" step="1"></label>
+    <label>Shutdown grace (s) <input type="number" name="shutdown_grace" min="0" max="600" step="0.5"></label>
+    <label>Terminate grace (s) <input type="number" name="term_grace" min="0" max="600" step="0.5"></label>
+    <label>Restart daily at <input type="time" name="schedule.restart_at"></label>
+    <label>Update on daily restart <input type="checkbox" name="schedule.update"></label>
+  </fieldset>
+  <fieldset>
+    <legend>Watchdog</legend>
+    <label>Restart ALAS when the WebUI hangs <input type="checkbox" name="watchdog.enabled"></label>
+    <label>Check every (s) <input type="number" name="watchdog.interval" min="5" max="3600" step="1"></label>
+    <label>Check timeout (s) <input type="number" name="watchdog.timeout" min="1" max="3600" step="1"></label>
+    <label>Failed checks before restart <input type="number" name="watchdog.failures" min="1" max="100" step="1"></label>
+  </fieldset>
+  <fieldset>
+    <legend>Tray</legend>
+    <label>Show tray icon <input type="checkbox" name="tray.enabled"></label>
+    <label>Close to tray <input type="checkbox" name="tray.close_to_tray"></label>
+  </fieldset>
+  <fieldset>
+    <legend>Notifications</legend>
+    <label>Show notifications <input type="checkbox" name="notifications.enabled"></label>
+    <label>Task finished <input type="checkbox" name="notifications.task_finished"></label>
+    <label>Errors <input type="checkbox" name="notifications.error"></label>
+    <label>Launcher problems <input type="checkbox" name="notifications.launcher"></label>
+    <label>Warnings and other notifications <input type="checkbox" name="notifications.other"></label>
+  </fieldset>
+  <fieldset>
+    <legend>Start at login</legend>
+    <label>Start at login <input type="checkbox" id="autostart"></label>
+    <label>Start minimized <input type="checkbox" id="autostart-minimized"></label>
+  </fieldset>
+  <fieldset>
+    <legend>Launcher</legend>
+    <label>Log level
+      <select name="log_level"><option>error</option><option>warn</option><option>info</option><option>debug</option><option>trace</option></select></label>
+    <label>Window width <input type="number" name="window.width" min="400" max="10000" step="1"></label>
+    <label>Window height <input type="number" name="window.height" min="300" max="10000" step="1"></label>
+  </fieldset>
+  <p>Changes other than "Close to tray", notifications and start at login take effect when the launcher is restarted.</p>
+  <button type="submit">Save</button>
+  <p id="message"></p>
+</form>
+<script>
+  const form = document.getElementById('settings');
+  const message = document.getElementById('message');
+  const invoke = window.__TAURI__.core.invoke;
+  const autostart = document.getElementById('autostart');
+  const autostartMinimized = document.getElementById('autostart-minimized');
+  let settings = {};
+  function fields(callback) {
+    for (const input of form.querySelectorAll('[name]')) {
+      const path = input.name.split('.');
+      const key = path.pop();
+      const parent = path.reduce((object, part) => object[part], settings);
+      callback(input, parent, key);
+    }
+  }
+  invoke('get_settings').then(loaded => {
+    settings = loaded;
+    fields((input, parent, key) => {
+      if (input.type === 'checkbox') input.checked = parent[key];
+      else input.value = parent[key] ?? '';
+    });
+  });
+  invoke('autostart_status').then(status => {
+    autostart.checked = status.enabled;
+    autostartMinimized.checked = status.minimized;
+  });
+  form.addEventListener('submit', event => {
+    event.preventDefault();
+    fields((input, parent, key) => {
+      if (input.type === 'checkbox') parent[key] = input.checked;
+      else if (input.type === 'number') parent[key] = Number(input.value);
+      else if (input.type === 'time') parent[key] = input.value || null;
+      else parent[key] = input.value;
+    });
+    invoke('save_settings', { settings }).then(() => invoke('set_autostart', {
+      enabled: autostart.checked,
+      minimized: autostartMinimized.checked,
+    })).then(() => {
+      message.className = '';
+      message.textContent = 'Saved';
+    }, e => {
+      message.className = 'error';
+      message.textContent = e;
+    });
+  });
+</script>
+</body>
+</html>"#;
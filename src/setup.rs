@@ -3,15 +3,18 @@ use serde_json::Value as JsonValue;
 use std::env::set_current_dir;
 use std::fs;
 use std::io::{BufReader, Read};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 use std::sync::mpsc::{self, Sender};
 use std::thread;
 use tracing::{info, warn};
 
-use crate::window_util::CreateNoWindow as _;
+use crate::{
+    state::{LauncherState, StateCell},
+    window_util::CreateNoWindow as _,
+};
 
-fn alas_repo_dir() -> PathBuf {
+pub fn alas_repo_dir() -> PathBuf {
     // Always check if this is a typical same-folder portable distribution
     let exe_folder = std::env::current_exe()
         .unwrap()
@@ -49,8 +52,8 @@ fn prepend_path_to_env(key: &str, path: PathBuf) {
 }
 
 #[cfg(unix)]
-pub fn setup_environment() -> Result<()> {
-    let dir = alas_repo_dir();
+pub fn setup_environment(repo: Option<&Path>) -> Result<()> {
+    let dir = repo.map(Path::to_path_buf).unwrap_or_else(alas_repo_dir);
     info!("ALAS dir is {:?}", &dir);
     set_current_dir(&dir)?;
     prepend_path_to_env("PATH", dir.join("toolkit").join("libexec").join("git-core"));
@@ -60,8 +63,8 @@ pub fn setup_environment() -> Result<()> {
 }
 
 #[cfg(windows)]
-pub fn setup_environment() -> Result<()> {
-    let dir = alas_repo_dir();
+pub fn setup_environment(repo: Option<&Path>) -> Result<()> {
+    let dir = repo.map(Path::to_path_buf).unwrap_or_else(alas_repo_dir);
     info!("ALAS dir is {:?}", &dir);
     set_current_dir(&dir)?;
     prepend_path_to_env("PATH", dir.join("toolkit").join("git").join("cmd"));
@@ -80,15 +83,26 @@ fn setup_git_ca_bundle() {
     }
 }
 
-pub fn setup_alas_repo(mut status_updater: impl FnMut(&str)) -> Result<()> {
+/// Prepares the repo like deploy/installer.py, `update` pulls the latest ALAS with git.
+pub fn setup_alas_repo(
+    state: &StateCell,
+    update: bool,
+    mut status_updater: impl FnMut(&str),
+) -> Result<()> {
     info!("Starting setup for ALAS repository...");
     #[cfg(target_os = "linux")]
     setup_git_ca_bundle();
     // Similar setup to deploy/installer.py
+    state.set(LauncherState::CleaningConfig);
     status_updater("Cleaning up config files");
     atomic_failure_cleanup("./config")?;
+    if !update {
+        info!("Skipping ALAS update");
+        return Ok(());
+    }
+    state.set(LauncherState::Updating { progress: None });
     status_updater("Updating ALAS");
-    git_update(status_updater)?;
+    git_update(state, status_updater)?;
     Ok(())
 }
 
@@ -139,7 +153,7 @@ fn pipe_lines(read: impl Read + Send + 'static, tx: Sender<(bool, String)>, is_e
     });
 }
 
-fn git_update(mut status_updater: impl FnMut(&str)) -> Result<()> {
+fn git_update(state: &StateCell, mut status_updater: impl FnMut(&str)) -> Result<()> {
     // Decorate execute() to get fetch progress
     let script = r#"
 import deploy.git
@@ -189,6 +203,9 @@ gm.git_install()
             let mut n = 0usize;
             if let Some(precentage) = find_percentage(&sanitized) {
                 n = (precentage / 2) as usize;
+                state.set(LauncherState::Updating {
+                    progress: Some(precentage),
+                });
             }
             let bar = "=".repeat(n) + &" ".repeat(50 - n);
             status_updater(&format!("Updating ALAS: {sanitized}\n[{bar}]"));
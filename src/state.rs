@@ -0,0 +1,143 @@
+use std::{fmt, sync::Mutex};
+
+use serde::Serialize;
+use tracing::info;
+
+/// What the launcher is doing, as reported to the WebUI, `ctl status` and logs.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum LauncherState {
+    Initializing,
+    CleaningConfig,
+    /// `progress` is the percentage of the current git step, if git reports one
+    Updating {
+        progress: Option<u8>,
+    },
+    StartingBackend,
+    Running {
+        port: u16,
+        pid: Option<u32>,
+    },
+    Stopping,
+    Stopped,
+    Failed {
+        reason: String,
+    },
+    ShuttingDown,
+}
+
+impl fmt::Display for LauncherState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Initializing => write!(f, "Initializing"),
+            Self::CleaningConfig => write!(f, "Cleaning up config files"),
+            Self::Updating { progress: None } => write!(f, "Updating ALAS"),
+            Self::Updating {
+                progress: Some(progress),
+            } => write!(f, "Updating ALAS ({progress}%)"),
+            Self::StartingBackend => write!(f, "Starting GUI"),
+            Self::Running { port, pid: None } => write!(f, "Running on port {port}"),
+            Self::Running {
+                port,
+                pid: Some(pid),
+            } => write!(f, "Running on port {port}, gui.py PID {pid}"),
+            Self::Stopping => write!(f, "Stopping ALAS"),
+            Self::Stopped => write!(f, "Stopped"),
+            Self::Failed { reason } => write!(f, "Failed: {reason}"),
+            Self::ShuttingDown => write!(f, "Shutting down"),
+        }
+    }
+}
+
+type Listener = Box<dyn Fn(&LauncherState) + Send + Sync>;
+
+/// The current `LauncherState`, notifying listeners whenever it changes.
+pub struct StateCell {
+    state: Mutex<LauncherState>,
+    listeners: Mutex<Vec<Listener>>,
+}
+
+impl Default for StateCell {
+    fn default() -> Self {
+        Self {
+            state: Mutex::new(LauncherState::Initializing),
+            listeners: Mutex::default(),
+        }
+    }
+}
+
+impl StateCell {
+    pub fn get(&self) -> LauncherState {
+        self.state.lock().unwrap().clone()
+    }
+
+    pub fn set(&self, state: LauncherState) {
+        {
+            let mut current = self.state.lock().unwrap();
+            if *current == state {
+                return;
+            }
+            *current = state.clone();
+        }
+        info!("Launcher state: {state}");
+        for listener in self.listeners.lock().unwrap().iter() {
+            listener(&state);
+        }
+    }
+
+    pub fn subscribe(&self, listener: impl Fn(&LauncherState) + Send + Sync + 'static) {
+        self.listeners.lock().unwrap().push(Box::new(listener));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_state_changes() {
+        let cell = StateCell::default();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let seen1 = seen.clone();
+        cell.subscribe(move |state| seen1.lock().unwrap().push(state.clone()));
+        cell.set(LauncherState::Initializing);
+        cell.set(LauncherState::Updating { progress: Some(10) });
+        cell.set(LauncherState::Updating { progress: Some(10) });
+        cell.set(LauncherState::Updating { progress: Some(20) });
+        cell.set(LauncherState::Running {
+            port: 22267,
+            pid: Some(42),
+        });
+        assert_eq!(
+            vec![
+                LauncherState::Updating { progress: Some(10) },
+                LauncherState::Updating { progress: Some(20) },
+                LauncherState::Running {
+                    port: 22267,
+                    pid: Some(42)
+                },
+            ],
+            *seen.lock().unwrap()
+        );
+        assert_eq!(
+            "Running on port 22267, gui.py PID 42",
+            cell.get().to_string()
+        );
+    }
+
+    #[test]
+    fn test_state_json() {
+        assert_eq!(
+            r#"{"state":"updating","progress":45}"#,
+            serde_json::to_string(&LauncherState::Updating { progress: Some(45) }).unwrap()
+        );
+        assert_eq!(
+            r#"{"state":"failed","reason":"no git"}"#,
+            serde_json::to_string(&LauncherState::Failed {
+                reason: "no git".to_owned()
+            })
+            .unwrap()
+        );
+    }
+}
@@ -0,0 +1,84 @@
+use std::sync::Mutex;
+
+use tauri::{
+    menu::{Menu, MenuItem, PredefinedMenuItem},
+    tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
+    App, AppHandle, Manager,
+};
+
+use crate::{launcher::Launcher, settings_window};
+
+pub const TRAY_ID: &str = "main";
+
+/// Last alert raised from the output of gui.py, until the window is opened
+static ALERT: Mutex<Option<String>> = Mutex::new(None);
+
+pub fn create(app: &App) -> tauri::Result<()> {
+    let show = MenuItem::with_id(app, "show", "Show ALAS", true, None::<&str>)?;
+    let restart = MenuItem::with_id(app, "restart", "Restart ALAS", true, None::<&str>)?;
+    let update = MenuItem::with_id(app, "update", "Update and restart ALAS", true, None::<&str>)?;
+    let settings = MenuItem::with_id(app, "settings", "Settings", true, None::<&str>)?;
+    let quit = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
+    let separator = PredefinedMenuItem::separator(app)?;
+    let menu = Menu::with_items(
+        app,
+        &[&show, &restart, &update, &separator, &settings, &quit],
+    )?;
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip("Alas")
+        .menu(&menu)
+        .show_menu_on_left_click(false)
+        .on_menu_event(|app, event| match event.id().as_ref() {
+            "show" => crate::show_main_window(app),
+            "restart" => {
+                let _ = crate::spawn_restart(app.clone(), false);
+            }
+            "update" => {
+                let _ = crate::spawn_restart(app.clone(), true);
+            }
+            "settings" => settings_window::open(app),
+            "quit" => app.exit(0),
+            _ => {}
+        })
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                ..
+            } = event
+            {
+                crate::show_main_window(tray.app_handle());
+            }
+        });
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    builder.build(app)?;
+    // Keep the launcher state in the tooltip
+    let handle = app.handle().clone();
+    app.state::<Launcher>()
+        .state()
+        .subscribe(move |_| update_tooltip(&handle));
+    Ok(())
+}
+
+/// Marks the tray icon with `alert`, or clears the mark.
+pub fn set_alert(app_handle: &AppHandle, alert: Option<&str>) {
+    *ALERT.lock().unwrap() = alert.map(str::to_owned);
+    if let Some(tray) = app_handle.tray_by_id(TRAY_ID) {
+        let _ = tray.set_title(alert.map(|_| "!"));
+    }
+    update_tooltip(app_handle);
+}
+
+fn update_tooltip(app_handle: &AppHandle) {
+    let Some(tray) = app_handle.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    let state = app_handle.state::<Launcher>().state().get();
+    let tooltip = match &*ALERT.lock().unwrap() {
+        Some(alert) => format!("Alas: {state}\n{alert}"),
+        None => format!("Alas: {state}"),
+    };
+    let _ = tray.set_tooltip(Some(tooltip));
+}
@@ -0,0 +1,218 @@
+use std::{fs, path::Path};
+
+use anyhow::{anyhow, Result};
+use base64::{prelude::BASE64_STANDARD, Engine};
+use serde::Serialize;
+use tauri::AppHandle;
+use tauri_plugin_dialog::DialogExt;
+use tracing::{error, info};
+
+/// Files are sent to the page as base64 over IPC, keep them reasonably small
+const MAX_FILE_SIZE: u64 = 32 * 1024 * 1024;
+
+/// MIME types of the files ALAS imports, by extension
+const MIME_TYPES: &[(&str, &str)] = &[
+    ("json", "application/json"),
+    ("yaml", "application/yaml"),
+    ("yml", "application/yaml"),
+    ("txt", "text/plain"),
+    ("log", "text/plain"),
+    ("csv", "text/csv"),
+    ("ini", "text/plain"),
+    ("png", "image/png"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("bmp", "image/bmp"),
+    ("webp", "image/webp"),
+    ("zip", "application/zip"),
+];
+
+#[derive(Debug, Serialize)]
+pub struct OpenedFile {
+    pub name: String,
+    pub mime: String,
+    pub data: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum OpenResult {
+    Opened { files: Vec<OpenedFile> },
+    Cancelled,
+    Failed { error: String },
+}
+
+/// File types allowed by an `<input type=file>` `accept` attribute.
+#[derive(Debug, Default, PartialEq)]
+pub struct Accept {
+    /// `.png` style entries, lowercase without the dot
+    extensions: Vec<String>,
+    /// `image/png` and `image/*` style entries
+    mime_types: Vec<String>,
+}
+
+impl Accept {
+    pub fn parse(accept: &str) -> Self {
+        let mut result = Self::default();
+        for entry in accept.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let entry = entry.to_ascii_lowercase();
+            match entry.strip_prefix('.') {
+                Some(extension) => result.extensions.push(extension.to_owned()),
+                None => result.mime_types.push(entry),
+            }
+        }
+        result
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.extensions.is_empty() && self.mime_types.is_empty()
+    }
+
+    /// Extensions to show in the file dialog, `None` if any file type would do.
+    pub fn dialog_extensions(&self) -> Option<Vec<String>> {
+        if self.is_empty() || self.mime_types.iter().any(|m| m == "*/*") {
+            return None;
+        }
+        let mut extensions = self.extensions.clone();
+        for (extension, _) in MIME_TYPES.iter().filter(|(_, mime)| self.allows_mime(mime)) {
+            if !extensions.iter().any(|e| e == extension) {
+                extensions.push((*extension).to_owned());
+            }
+        }
+        Some(extensions)
+    }
+
+    pub fn allows(&self, name: &str) -> bool {
+        if self.is_empty() {
+            return true;
+        }
+        let extension = extension(name);
+        self.extensions.contains(&extension) || self.allows_mime(mime_type(&extension))
+    }
+
+    fn allows_mime(&self, mime: &str) -> bool {
+        self.mime_types.iter().any(|accepted| {
+            accepted == "*/*"
+                || accepted == mime
+                || accepted
+                    .strip_suffix("/*")
+                    .is_some_and(|kind| mime.split('/').next() == Some(kind))
+        })
+    }
+}
+
+fn extension(name: &str) -> String {
+    Path::new(name)
+        .extension()
+        .map(|e| e.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default()
+}
+
+fn mime_type(extension: &str) -> &'static str {
+    MIME_TYPES
+        .iter()
+        .find(|(e, _)| *e == extension)
+        .map_or("application/octet-stream", |(_, mime)| mime)
+}
+
+/// Lets the WebUI read local files picked in a native dialog, see the `<input type=file>` hook
+/// in `page_load_injector`.
+#[tauri::command]
+pub async fn open_file(
+    app_handle: AppHandle,
+    accept: Option<String>,
+    multiple: bool,
+) -> OpenResult {
+    let task = tauri::async_runtime::spawn_blocking(move || {
+        let accept = Accept::parse(accept.as_deref().unwrap_or_default());
+        let mut dialog = app_handle.dialog().file();
+        if let Some(extensions) = accept.dialog_extensions() {
+            let extensions = extensions.iter().map(String::as_str).collect::<Vec<_>>();
+            dialog = dialog.add_filter("Supported files", &extensions);
+        }
+        let paths = if multiple {
+            dialog.blocking_pick_files()
+        } else {
+            dialog.blocking_pick_file().map(|path| vec![path])
+        };
+        let Some(paths) = paths else {
+            return OpenResult::Cancelled;
+        };
+        let result: Result<Vec<_>> = paths
+            .iter()
+            .map(|path| {
+                let path = path
+                    .as_path()
+                    .ok_or_else(|| anyhow!("Invalid file path {path:?}"))?;
+                read_file(path, &accept)
+            })
+            .collect();
+        match result {
+            Ok(files) => OpenResult::Opened { files },
+            Err(e) => {
+                error!("Failed to open file: {e}");
+                OpenResult::Failed {
+                    error: e.to_string(),
+                }
+            }
+        }
+    });
+    task.await.unwrap_or_else(|e| OpenResult::Failed {
+        error: e.to_string(),
+    })
+}
+
+fn read_file(path: &Path, accept: &Accept) -> Result<OpenedFile> {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .ok_or_else(|| anyhow!("Invalid file path {}", path.display()))?;
+    if !accept.allows(&name) {
+        return Err(anyhow!("{name} is not a supported file type"));
+    }
+    let size = fs::metadata(path)?.len();
+    if size > MAX_FILE_SIZE {
+        return Err(anyhow!(
+            "{name} is too large ({} MiB, at most {} MiB)",
+            size / 1024 / 1024,
+            MAX_FILE_SIZE / 1024 / 1024
+        ));
+    }
+    let data = fs::read(path)?;
+    info!("Opened {} ({} bytes)", path.display(), data.len());
+    Ok(OpenedFile {
+        mime: mime_type(&extension(&name)).to_owned(),
+        name,
+        data: BASE64_STANDARD.encode(data),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accept() {
+        let any = Accept::parse("");
+        assert!(any.allows("config.json"));
+        assert_eq!(None, any.dialog_extensions());
+
+        let accept = Accept::parse(".json, image/*");
+        assert!(accept.allows("alas.JSON"));
+        assert!(accept.allows("screenshot.png"));
+        assert!(!accept.allows("alas.yaml"));
+        assert!(!accept.allows("noextension"));
+        assert_eq!(
+            Some(vec!["json", "png", "jpg", "jpeg", "gif", "bmp", "webp"]),
+            accept
+                .dialog_extensions()
+                .as_ref()
+                .map(|e| e.iter().map(String::as_str).collect::<Vec<_>>())
+        );
+
+        let accept = Accept::parse("application/yaml");
+        assert!(accept.allows("a.yml"));
+        assert!(!accept.allows("a.txt"));
+    }
+}
@@ -0,0 +1,218 @@
+use std::{
+    io::{Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    thread,
+    time::Duration,
+};
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use tracing::{error, info, warn};
+
+use crate::{
+    host::Host, launcher::Launcher, notify::NotificationKind, settings::WatchdogSettings,
+    state::LauncherState, webui::Target,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthEventKind {
+    /// The WebUI didn't answer a probe
+    ProbeFailed,
+    /// The WebUI answered again before the threshold was reached
+    Recovered,
+    Restarted,
+    RestartFailed,
+}
+
+/// Something the watchdog saw or did, kept for `ctl health`.
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthEvent {
+    pub time: String,
+    pub kind: HealthEventKind,
+    pub detail: String,
+}
+
+impl HealthEvent {
+    pub fn new(kind: HealthEventKind, detail: impl Into<String>) -> Self {
+        Self {
+            time: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            kind,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Status code of an HTTP response's status line, e.g. `HTTP/1.1 200 OK`.
+pub fn parse_status_line(line: &str) -> Option<u16> {
+    let mut parts = line.split_whitespace();
+    if !parts.next()?.starts_with("HTTP/") {
+        return None;
+    }
+    parts.next()?.parse().ok()
+}
+
+/// Requests the WebUI's front page, anything but a server error counts as healthy.
+pub fn probe(address: &str, timeout: Duration) -> Result<()> {
+    let socket_address = address
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| anyhow!("Cannot resolve {address}"))?;
+    let mut stream = TcpStream::connect_timeout(&socket_address, timeout)?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+    write!(
+        stream,
+        "GET / HTTP/1.1\r\nHost: {address}\r\nConnection: close\r\n\r\n"
+    )?;
+    // The status line is all that's needed
+    let mut response = Vec::new();
+    let mut buffer = [0; 256];
+    while !response.contains(&b'\n') {
+        let read = stream.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        response.extend_from_slice(&buffer[..read]);
+    }
+    let response = String::from_utf8_lossy(&response);
+    let line = response.lines().next().unwrap_or_default();
+    match parse_status_line(line) {
+        Some(status) if status < 500 => Ok(()),
+        Some(status) => Err(anyhow!("WebUI answered with status {status}")),
+        None => Err(anyhow!("WebUI answered with {line:?}")),
+    }
+}
+
+/// Counts failed probes of the WebUI gui.py serves.
+pub struct Watchdog {
+    settings: WatchdogSettings,
+    failures: u32,
+}
+
+impl Watchdog {
+    pub fn new(settings: &WatchdogSettings) -> Self {
+        Self {
+            settings: settings.clone(),
+            failures: 0,
+        }
+    }
+
+    /// Probes the WebUI if gui.py is supposed to be serving it, true once it failed often enough
+    /// in a row that gui.py should be restarted.
+    pub fn check(&mut self, launcher: &Launcher) -> bool {
+        let running = matches!(launcher.state().get(), LauncherState::Running { .. });
+        let Target::Local(options) = launcher.target() else {
+            return false;
+        };
+        if !running || launcher.is_busy() {
+            self.failures = 0;
+            return false;
+        }
+        match probe(
+            &options.connect_address(),
+            Duration::from_secs(self.settings.timeout),
+        ) {
+            Ok(()) => {
+                if self.failures > 0 {
+                    info!("WebUI is answering again");
+                    launcher.record_health(HealthEvent::new(
+                        HealthEventKind::Recovered,
+                        format!("after {} failed probes", self.failures),
+                    ));
+                }
+                self.failures = 0;
+                false
+            }
+            Err(e) => {
+                self.failures += 1;
+                warn!(
+                    "WebUI health probe failed ({}/{}): {e}",
+                    self.failures, self.settings.failures
+                );
+                launcher.record_health(HealthEvent::new(
+                    HealthEventKind::ProbeFailed,
+                    format!("{}/{}: {e}", self.failures, self.settings.failures),
+                ));
+                if self.failures < self.settings.failures {
+                    return false;
+                }
+                self.failures = 0;
+                true
+            }
+        }
+    }
+}
+
+/// Probes the WebUI in the background, restarting gui.py when it hangs.
+pub fn start(host: impl Host, settings: &WatchdogSettings) {
+    if !settings.enabled {
+        return;
+    }
+    let interval = settings.interval();
+    let mut watchdog = Watchdog::new(settings);
+    thread::spawn(move || loop {
+        thread::sleep(interval);
+        let launcher = host.launcher();
+        if !watchdog.check(launcher) {
+            continue;
+        }
+        warn!("WebUI stopped answering, restarting gui.py...");
+        match host.restart(false) {
+            Ok(()) => {
+                info!("Restarted gui.py after it stopped answering");
+                launcher.record_health(HealthEvent::new(HealthEventKind::Restarted, ""));
+                // Failures are notified as a failed launcher state
+                host.notify(
+                    NotificationKind::Launcher,
+                    "ALAS restarted",
+                    "The WebUI stopped answering",
+                );
+            }
+            Err(e) => {
+                error!("Failed to restart gui.py after it stopped answering: {e}");
+                launcher.record_health(HealthEvent::new(
+                    HealthEventKind::RestartFailed,
+                    e.to_string(),
+                ));
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_parse_status_line() {
+        assert_eq!(Some(200), parse_status_line("HTTP/1.1 200 OK"));
+        assert_eq!(Some(502), parse_status_line("HTTP/1.0 502 Bad Gateway"));
+        assert_eq!(None, parse_status_line("SSH-2.0-OpenSSH_9.6"));
+        assert_eq!(None, parse_status_line(""));
+    }
+
+    #[test]
+    fn test_probe() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let server = thread::spawn(move || {
+            for response in ["HTTP/1.1 200 OK\r\n\r\n", "HTTP/1.1 500 Oops\r\n\r\n", ""] {
+                let (mut stream, _) = listener.accept().unwrap();
+                let _ = stream.read(&mut [0; 1024]);
+                if response.is_empty() {
+                    // Hung: accepts but never answers
+                    thread::sleep(Duration::from_millis(500));
+                } else {
+                    stream.write_all(response.as_bytes()).unwrap();
+                }
+            }
+        });
+        let timeout = Duration::from_millis(200);
+        assert!(probe(&address, timeout).is_ok());
+        assert!(probe(&address, timeout).is_err());
+        assert!(probe(&address, timeout).is_err());
+        server.join().unwrap();
+    }
+}
@@ -0,0 +1,339 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use anyhow::{anyhow, Result};
+use serde_json::Value as JsonValue;
+use tauri::Url;
+use tracing::warn;
+
+const DEFAULT_PORT: u16 = 22267;
+
+/// The `Deploy.Webui` section of deploy.yaml, as far as it maps to gui.py arguments.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WebuiOptions {
+    /// Address gui.py binds to
+    pub host: String,
+    pub port: u16,
+    pub password: Option<String>,
+    pub cdn: bool,
+    /// Configs to run on startup
+    pub run: Vec<String>,
+    /// `WebuiHost` from deploy.yaml when it was ignored, gui.py isn't exposed without a password
+    pub ignored_host: Option<String>,
+}
+
+impl Default for WebuiOptions {
+    fn default() -> Self {
+        Self {
+            host: "127.0.0.1".to_owned(),
+            port: DEFAULT_PORT,
+            password: None,
+            cdn: false,
+            run: Vec::new(),
+            ignored_host: None,
+        }
+    }
+}
+
+impl WebuiOptions {
+    pub fn from_deploy_config(config: Option<&JsonValue>) -> Self {
+        let mut options = Self::default();
+        let webui = config
+            .and_then(|config| config.get("Deploy"))
+            .and_then(|deploy| deploy.get("Webui"));
+        let Some(webui) = webui else {
+            warn!("Deploy.Webui not found in config, using default port {DEFAULT_PORT}");
+            return options;
+        };
+        match webui.get("WebuiPort").and_then(JsonValue::as_u64) {
+            Some(port) => options.port = port as u16,
+            None => warn!("WebuiPort not found in config, using default port {DEFAULT_PORT}"),
+        }
+        options.password = webui
+            .get("Password")
+            .and_then(JsonValue::as_str)
+            .filter(|password| !password.is_empty())
+            .map(str::to_owned);
+        if let Some(host) = webui.get("WebuiHost").and_then(JsonValue::as_str) {
+            let host = host.trim();
+            if !host.is_empty() {
+                if is_exposed(host) && options.password.is_none() {
+                    options.ignored_host = Some(host.to_owned());
+                } else {
+                    options.host = host.to_owned();
+                }
+            }
+        }
+        // A CDN URL is read by gui.py from deploy.yaml itself, only the flag can be passed
+        options.cdn = webui.get("CDN").and_then(JsonValue::as_bool) == Some(true);
+        options.run = webui.get("Run").map(parse_run).unwrap_or_default();
+        options
+    }
+
+    /// Arguments for gui.py, with `extra_run` appended to the configs from deploy.yaml.
+    pub fn args(&self, extra_run: &[String]) -> Vec<String> {
+        let mut args = vec![
+            "--host".to_owned(),
+            self.host.clone(),
+            "--port".to_owned(),
+            self.port.to_string(),
+        ];
+        // gui.py reads the password from deploy.yaml itself, --key would show it in `ps`
+        if self.cdn {
+            args.push("--cdn".to_owned());
+        }
+        let mut run = self.run.clone();
+        run.extend(extra_run.iter().filter(|c| !self.run.contains(c)).cloned());
+        if !run.is_empty() {
+            args.push("--run".to_owned());
+            args.extend(run);
+        }
+        args
+    }
+
+    /// Host the webview and health checks connect to, loopback if gui.py binds to every address.
+    pub fn connect_host(&self) -> String {
+        match self.host.parse::<IpAddr>() {
+            Ok(IpAddr::V4(ip)) if ip.is_unspecified() => Ipv4Addr::LOCALHOST.to_string(),
+            Ok(IpAddr::V6(ip)) if ip.is_unspecified() => format!("[{}]", Ipv6Addr::LOCALHOST),
+            Ok(IpAddr::V6(ip)) => format!("[{ip}]"),
+            _ => self.host.clone(),
+        }
+    }
+
+    pub fn connect_address(&self) -> String {
+        format!("{}:{}", self.connect_host(), self.port)
+    }
+
+    pub fn url(&self) -> Url {
+        Url::parse(&format!("http://{}/", self.connect_address())).unwrap()
+    }
+
+    /// Script logging the launcher window in, only given to loopback pages so the password
+    /// never leaves the host.
+    pub fn login_script(&self) -> Option<String> {
+        let password = self.password.as_ref()?;
+        let is_loopback = match self
+            .connect_host()
+            .trim_matches(['[', ']'])
+            .parse::<IpAddr>()
+        {
+            Ok(ip) => ip.is_loopback(),
+            Err(_) => self.connect_host() == "localhost",
+        };
+        is_loopback.then(|| login_script(&self.url(), password))
+    }
+
+    /// Why `WebuiHost` was ignored, to be shown to the user.
+    pub fn ignored_host_warning(&self) -> Option<String> {
+        let host = self.ignored_host.as_ref()?;
+        Some(format!(
+            "WebuiHost {host} in config/deploy.yaml is ignored because Password is not set, the ALAS WebUI is only reachable from this machine on {}. Set Deploy.Webui.Password to allow other machines.",
+            self.connect_address()
+        ))
+    }
+}
+
+/// An ALAS WebUI run by someone else, e.g. on a home server.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemoteTarget {
+    pub url: Url,
+    pub password: Option<String>,
+}
+
+impl RemoteTarget {
+    pub fn new(url: &str, password: Option<String>) -> Result<Self> {
+        let url = Url::parse(url).map_err(|e| anyhow!("Invalid remote URL {url}: {e}"))?;
+        if !matches!(url.scheme(), "http" | "https") || url.host_str().is_none() {
+            return Err(anyhow!(
+                "Remote URL must be http(s)://host[:port]/, got {url}"
+            ));
+        }
+        Ok(Self { url, password })
+    }
+
+    pub fn port(&self) -> u16 {
+        self.url.port_or_known_default().unwrap_or(80)
+    }
+
+    pub fn connect_address(&self) -> String {
+        // IPv6 hosts keep their brackets
+        format!(
+            "{}:{}",
+            self.url.host_str().unwrap_or_default(),
+            self.port()
+        )
+    }
+
+    /// Script logging the launcher window in, the password is only sent to the configured origin.
+    pub fn login_script(&self) -> Option<String> {
+        let password = self.password.as_ref()?;
+        Some(login_script(&self.url, password))
+    }
+}
+
+/// Where the launcher window points: a gui.py we run ourselves, or a remote one.
+pub enum Target {
+    Local(WebuiOptions),
+    Remote(RemoteTarget),
+}
+
+impl Target {
+    pub fn url(&self) -> Url {
+        match self {
+            Self::Local(options) => options.url(),
+            Self::Remote(remote) => remote.url.clone(),
+        }
+    }
+
+    pub fn port(&self) -> u16 {
+        match self {
+            Self::Local(options) => options.port,
+            Self::Remote(remote) => remote.port(),
+        }
+    }
+
+    pub fn login_script(&self) -> Option<String> {
+        match self {
+            Self::Local(options) => options.login_script(),
+            Self::Remote(remote) => remote.login_script(),
+        }
+    }
+}
+
+// Whether other machines can reach gui.py bound to `host`
+fn is_exposed(host: &str) -> bool {
+    match host.parse::<IpAddr>() {
+        Ok(ip) => !ip.is_loopback(),
+        Err(_) => host != "localhost",
+    }
+}
+
+// Stores the password where pywebio's login form looks for it, so the prompt is skipped
+fn login_script(url: &Url, password: &str) -> String {
+    let origin = url.origin().ascii_serialization();
+    format!(
+        "if (location.origin === {}) {{ localStorage.setItem('password', {}); }}",
+        serde_json::to_string(&origin).unwrap(),
+        serde_json::to_string(password).unwrap()
+    )
+}
+
+// Run is either a list, or a string holding one like '["alas","alas2"]'
+fn parse_run(value: &JsonValue) -> Vec<String> {
+    let list = match value {
+        JsonValue::String(s) => serde_json::from_str(s).unwrap_or_else(|_| {
+            warn!("Invalid Deploy.Webui.Run {s:?}, ignoring it");
+            JsonValue::Null
+        }),
+        other => other.clone(),
+    };
+    list.as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|config| config.as_str().map(str::to_owned))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(yaml: &str) -> WebuiOptions {
+        let config: JsonValue = serde_yaml::from_str(yaml).unwrap();
+        WebuiOptions::from_deploy_config(Some(&config))
+    }
+
+    #[test]
+    fn test_webui_args() {
+        let options = parse(
+            r#"
+Deploy:
+  Webui:
+    WebuiHost: 0.0.0.0
+    WebuiPort: 12345
+    Password: secret
+    CDN: true
+    Run: '["alas"]'
+"#,
+        );
+        assert_eq!(
+            vec!["--host", "0.0.0.0", "--port", "12345", "--cdn", "--run", "alas", "alas2"],
+            options.args(&["alas".to_owned(), "alas2".to_owned()])
+        );
+        assert_eq!("http://127.0.0.1:12345/", options.url().as_str());
+        assert!(is_exposed(&options.host));
+        assert_eq!(
+            Some(
+                r#"if (location.origin === "http://127.0.0.1:12345") { localStorage.setItem('password', "secret"); }"#
+                    .to_owned()
+            ),
+            options.login_script()
+        );
+    }
+
+    #[test]
+    fn test_webui_defaults() {
+        let options = parse(
+            r#"
+Deploy:
+  Webui:
+    WebuiHost: null
+    WebuiPort: 22267
+    Password: null
+    CDN: 'https://cdn.example.com'
+    Run: null
+"#,
+        );
+        assert_eq!(WebuiOptions::default(), options);
+        assert_eq!(
+            vec!["--host", "127.0.0.1", "--port", "22267"],
+            options.args(&[])
+        );
+        assert!(!is_exposed(&options.host));
+        assert_eq!(None, options.login_script());
+        assert_eq!(None, options.ignored_host_warning());
+        assert_eq!(
+            None,
+            parse("Deploy: {Webui: {WebuiHost: 192.168.1.2, Password: secret}}").login_script()
+        );
+        assert_eq!(
+            vec!["alas", "alas2"],
+            parse("Deploy: {Webui: {Run: [alas, alas2]}}").run
+        );
+        assert_eq!(
+            "http://[::1]:22267/",
+            parse("Deploy: {Webui: {WebuiHost: '::', Password: secret}}")
+                .url()
+                .as_str()
+        );
+    }
+
+    #[test]
+    fn test_webui_host_without_password() {
+        let options = parse("Deploy: {Webui: {WebuiHost: 0.0.0.0, Password: null}}");
+        assert_eq!("127.0.0.1", options.host);
+        assert!(!is_exposed(&options.host));
+        assert_eq!(Some("0.0.0.0".to_owned()), options.ignored_host);
+        assert!(options.ignored_host_warning().is_some());
+        let options = parse("Deploy: {Webui: {WebuiHost: '::', Password: ''}}");
+        assert_eq!("127.0.0.1", options.host);
+        let options = parse("Deploy: {Webui: {WebuiHost: localhost}}");
+        assert_eq!("localhost", options.host);
+        assert_eq!(None, options.ignored_host);
+    }
+
+    #[test]
+    fn test_remote_target() {
+        let remote = RemoteTarget::new("https://alas.example.com/", None).unwrap();
+        assert_eq!("alas.example.com:443", remote.connect_address());
+        assert_eq!(None, remote.login_script());
+        let remote = RemoteTarget::new("http://[fd00::2]:22267", Some("pw".to_owned())).unwrap();
+        assert_eq!("[fd00::2]:22267", remote.connect_address());
+        assert!(remote
+            .login_script()
+            .unwrap()
+            .contains(r#""http://[fd00::2]:22267""#));
+        assert!(RemoteTarget::new("file:///tmp/alas", None).is_err());
+        assert!(RemoteTarget::new("192.168.1.2:22267", None).is_err());
+    }
+}
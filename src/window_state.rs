@@ -0,0 +1,239 @@
+use std::{collections::HashMap, fs, path::PathBuf};
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use tauri::{Monitor, PhysicalPosition, PhysicalSize, WebviewWindow};
+use tracing::{info, warn};
+
+/// Smallest part of the title bar that has to be on a monitor to restore a position
+const MIN_VISIBLE: (i32, i32) = (100, 40);
+
+/// Main window geometry in physical pixels, remembered per monitor.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Geometry {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    #[serde(default)]
+    pub maximized: bool,
+    #[serde(default)]
+    pub fullscreen: bool,
+}
+
+/// A monitor's bounds, in physical pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Area {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// `window-state.yaml` in the launcher's config folder.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct WindowState {
+    /// Monitor the window was last closed on
+    #[serde(default)]
+    last_monitor: Option<String>,
+    #[serde(default)]
+    monitors: HashMap<String, Geometry>,
+}
+
+impl WindowState {
+    fn path() -> Option<PathBuf> {
+        Some(
+            dirs::config_dir()?
+                .join("alas-launcher")
+                .join("window-state.yaml"),
+        )
+    }
+
+    fn load() -> Self {
+        let Some(content) = Self::path().and_then(|path| fs::read_to_string(path).ok()) else {
+            return Self::default();
+        };
+        serde_yaml::from_str(&content).unwrap_or_else(|e| {
+            warn!("Ignoring invalid window state: {e}");
+            Self::default()
+        })
+    }
+
+    fn save(&self) -> Result<()> {
+        let path = Self::path().ok_or_else(|| anyhow!("No config folder on this system"))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_yaml::to_string(self)?)?;
+        Ok(())
+    }
+}
+
+fn monitor_key(monitor: &Monitor) -> String {
+    let size = monitor.size();
+    let position = monitor.position();
+    match monitor.name() {
+        Some(name) => name.clone(),
+        None => format!(
+            "{}x{}@{},{}",
+            size.width, size.height, position.x, position.y
+        ),
+    }
+}
+
+fn monitor_area(monitor: &Monitor) -> Area {
+    Area {
+        x: monitor.position().x,
+        y: monitor.position().y,
+        width: monitor.size().width,
+        height: monitor.size().height,
+    }
+}
+
+/// Moves the window back to where it was last closed, if that's still on a connected monitor.
+pub fn restore(window: &WebviewWindow) {
+    let state = WindowState::load();
+    let Ok(monitors) = window.available_monitors() else {
+        return;
+    };
+    // The monitor it was closed on, or any other one it was used on before
+    let saved = state
+        .last_monitor
+        .iter()
+        .chain(state.monitors.keys())
+        .find_map(|key| {
+            let monitor = monitors.iter().find(|m| &monitor_key(m) == key)?;
+            Some((state.monitors.get(key)?, monitor_area(monitor)))
+        });
+    let Some((saved, area)) = saved else {
+        return;
+    };
+    let Some(geometry) = fit(
+        saved,
+        &monitors.iter().map(monitor_area).collect::<Vec<_>>(),
+        area,
+    ) else {
+        return;
+    };
+    info!("Restoring main window to {geometry:?}");
+    let _ = window.set_size(PhysicalSize::new(geometry.width, geometry.height));
+    let _ = window.set_position(PhysicalPosition::new(geometry.x, geometry.y));
+    if geometry.maximized {
+        let _ = window.maximize();
+    }
+    if geometry.fullscreen {
+        let _ = window.set_fullscreen(true);
+    }
+}
+
+/// Remembers where the window is, for the monitor it's on.
+pub fn save(window: &WebviewWindow) {
+    if !window.is_visible().unwrap_or(false) || window.is_minimized().unwrap_or(false) {
+        return;
+    }
+    let Ok(Some(monitor)) = window.current_monitor() else {
+        return;
+    };
+    let key = monitor_key(&monitor);
+    let mut state = WindowState::load();
+    let maximized = window.is_maximized().unwrap_or(false);
+    let fullscreen = window.is_fullscreen().unwrap_or(false);
+    let geometry = match state.monitors.get(&key) {
+        // Keep the size to go back to when un-maximized
+        Some(previous) if maximized || fullscreen => Geometry {
+            maximized,
+            fullscreen,
+            ..*previous
+        },
+        _ => {
+            let (Ok(position), Ok(size)) = (window.outer_position(), window.inner_size()) else {
+                return;
+            };
+            Geometry {
+                x: position.x,
+                y: position.y,
+                width: size.width,
+                height: size.height,
+                maximized,
+                fullscreen,
+            }
+        }
+    };
+    state.monitors.insert(key.clone(), geometry);
+    state.last_monitor = Some(key);
+    if let Err(e) = state.save() {
+        warn!("Failed to save window state: {e}");
+    }
+}
+
+/// Shrinks `saved` to fit `area`, the monitor it was saved for, and drops the position if the
+/// title bar wouldn't be reachable on any of `areas`.
+pub fn fit(saved: &Geometry, areas: &[Area], area: Area) -> Option<Geometry> {
+    if saved.width == 0 || saved.height == 0 {
+        return None;
+    }
+    let mut geometry = *saved;
+    geometry.width = geometry.width.min(area.width);
+    geometry.height = geometry.height.min(area.height);
+    let reachable = areas.iter().any(|a| {
+        let overlap =
+            (geometry.x + geometry.width as i32).min(a.x + a.width as i32) - geometry.x.max(a.x);
+        overlap >= MIN_VISIBLE.0
+            && geometry.y >= a.y
+            && geometry.y + MIN_VISIBLE.1 <= a.y + a.height as i32
+    });
+    if !reachable {
+        // Center it on its monitor instead
+        geometry.x = area.x + (area.width - geometry.width) as i32 / 2;
+        geometry.y = area.y + (area.height - geometry.height) as i32 / 2;
+    }
+    Some(geometry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AREA: Area = Area {
+        x: 0,
+        y: 0,
+        width: 1920,
+        height: 1040,
+    };
+
+    fn geometry(x: i32, y: i32, width: u32, height: u32) -> Geometry {
+        Geometry {
+            x,
+            y,
+            width,
+            height,
+            maximized: false,
+            fullscreen: false,
+        }
+    }
+
+    #[test]
+    fn test_fit_window() {
+        let saved = geometry(100, 50, 1280, 880);
+        assert_eq!(Some(saved), fit(&saved, &[AREA], AREA));
+        // Too big for the monitor
+        assert_eq!(
+            Some(geometry(0, 0, 1920, 1040)),
+            fit(&geometry(0, 0, 2560, 1400), &[AREA], AREA)
+        );
+        // Off-screen to the right, or title bar above the monitor
+        assert_eq!(
+            Some(geometry(320, 80, 1280, 880)),
+            fit(&geometry(1900, 50, 1280, 880), &[AREA], AREA)
+        );
+        assert_eq!(
+            Some(geometry(320, 80, 1280, 880)),
+            fit(&geometry(100, -20, 1280, 880), &[AREA], AREA)
+        );
+        // On a second monitor that is still connected
+        let right = Area { x: 1920, ..AREA };
+        let saved = geometry(2000, 50, 1280, 880);
+        assert_eq!(Some(saved), fit(&saved, &[AREA, right], right));
+        assert_eq!(None, fit(&geometry(0, 0, 0, 0), &[AREA], AREA));
+    }
+}
@@ -25,15 +25,40 @@ impl CreateNoWindow for Command {
     }
 }
 
+// Process groups also get a console process group of their own, so Ctrl-Break only reaches them
 #[cfg(windows)]
 impl<T> CreateNoWindow for CommandGroupBuilder<'_, T> {
     fn create_no_window(&mut self) -> &mut Self {
-        use winapi::um::winbase::CREATE_NO_WINDOW;
+        use winapi::um::winbase::{CREATE_NEW_PROCESS_GROUP, CREATE_NO_WINDOW};
         if !HAS_CONSOLE.load(Ordering::Relaxed) {
-            self.creation_flags(CREATE_NO_WINDOW)
+            self.creation_flags(CREATE_NEW_PROCESS_GROUP | CREATE_NO_WINDOW)
         } else {
-            self
+            self.creation_flags(CREATE_NEW_PROCESS_GROUP)
+        }
+    }
+}
+
+/// Sends Ctrl-Break to the console process group `pid` leads.
+#[cfg(windows)]
+pub fn send_ctrl_break(pid: u32) -> std::io::Result<()> {
+    use winapi::um::wincon::{
+        AttachConsole, FreeConsole, GenerateConsoleCtrlEvent, CTRL_BREAK_EVENT,
+    };
+    // Without a console of our own, the hidden one the process runs in is borrowed
+    let borrow = !HAS_CONSOLE.load(Ordering::Relaxed);
+    unsafe {
+        if borrow && AttachConsole(pid) == 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        let result = if GenerateConsoleCtrlEvent(CTRL_BREAK_EVENT, pid) != 0 {
+            Ok(())
+        } else {
+            Err(std::io::Error::last_os_error())
+        };
+        if borrow {
+            FreeConsole();
         }
+        result
     }
 }
 